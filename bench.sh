@@ -1,4 +1,13 @@
 res=results.csv
+
+# HEADLESS=1 renders offscreen, for machines without a display
+features=""
+headless=""
+if [[ -n "$HEADLESS" ]]; then
+	features="--features headless"
+	headless="--headless"
+fi
+
 if [ -f $res ]; then
 	while true; do
 		read -p "$res already exists, should I remove it? " yn
@@ -10,39 +19,13 @@ if [ -f $res ]; then
 	done
 fi
 
-for file in shaders/nm_*.glsl; do
-	v="null"
-	case $file in 
-		*nm_rotor.glsl)
-			v=rotor;;
-		*nm_motor.glsl)
-			v=motor;;
-		*bitang*)
-			v=normtang;;
-		*matrix*)
-			v=matrix;;
-		*outer_log_motor*)
-			v=outermotor;;
-		*outer_log_rotor*)
-			v=outerrotor;;
-		*qtang*)
-			v=qrotor;;
-		#*cayley_motor*)
-			#v=cayleymotor;;
-		*cayley_rotor*)
-			v=cayleyrotor;;
-	esac
-	if [[ "$v" == "null" ]] 
-	then
-		continue
-	fi
-	echo "[TEST] $file"
-	cargo run --bin bench --release --  -w 1600 -h 1600 -p $file -v $v >/dev/null 2>&1
-	if [ $? -ne 0 ]; then
-		echo "[ERROR] $file"
-		exit
-	fi
-done
+# SUITE=other.toml runs another suite than bench.toml
+suite=${SUITE:-bench.toml}
+echo "[SUITE] $suite"
+cargo run --bin bench --release $features -- --suite $suite $headless
+if [ $? -ne 0 ]; then
+	echo "[ERROR] $suite"
+	exit
+fi
 
-python prep.py
-python visual.py
+cargo run --bin bench --release -- report
@@ -11,9 +11,9 @@ out struct {
 } vs;
 
 void main() {
-	gl_Position = app.viewProjection *  uModel * vec4(aPos, 1.0);
-	vs.normal = aNormal;
-	vs.fragWorldPos = (uModel * vec4(aPos, 1.0)).xyz;
+	gl_Position = app.viewProjection *  MODEL_MATRIX * vec4(aPos, 1.0);
+	vs.normal = NORMAL_MATRIX * aNormal;
+	vs.fragWorldPos = (MODEL_MATRIX * vec4(aPos, 1.0)).xyz;
 }
 
 #type fragment
@@ -34,14 +34,24 @@ uniform mat4 uModel = mat4(1, 0, 0, 0,
 						   0, 1, 0, 0,
 						   0, 0, 1, 0,
 						   0, 0, 0, 1);
+// Inverse transpose of uModel, only the upper 3x3 part is used.
+uniform mat4 uNormalMatrix = mat4(1, 0, 0, 0,
+								  0, 1, 0, 0,
+								  0, 0, 1, 0,
+								  0, 0, 0, 1);
+// The rigid part of uModel as a motor, p1 and p2 of a ppga_motor.
+uniform vec4 uModelMotorP1 = vec4(1, 0, 0, 0);
+uniform vec4 uModelMotorP2 = vec4(0, 0, 0, 0);
 
 
+// BEGIN GENERATED ATTRIBUTES (src/layout.rs)
 #ifdef NORMTANG_ATTRIBUTES
 #undef DEFAULT_ATTRIBUTES
 layout (location = 0) in vec3 aPos;
 layout (location = 1) in vec2 aUV;
 layout (location = 2) in vec3 aNormal;
 layout (location = 3) in vec3 aTangent;
+layout (location = 4) in float aTangentHandedness;
 #endif
 
 #ifdef MATRIX_ATTRIBUTES
@@ -58,6 +68,7 @@ layout (location = 4) in vec3 aBiTangent;
 layout (location = 0) in vec3 aPos;
 layout (location = 1) in vec2 aUV;
 layout (location = 2) in vec4 aTangentToModelSpaceRotor;
+layout (location = 3) in float aTangentHandedness;
 #endif
 
 #ifdef OUTER_ROTOR_ATTRIBUTES
@@ -65,6 +76,7 @@ layout (location = 2) in vec4 aTangentToModelSpaceRotor;
 layout (location = 0) in vec3 aPos;
 layout (location = 1) in vec2 aUV;
 layout (location = 2) in vec3 aTangentToModelSpaceOuterRotor;
+layout (location = 3) in float aTangentHandedness;
 #endif
 
 #ifdef CAYLEY_ROTOR_ATTRIBUTES
@@ -72,6 +84,7 @@ layout (location = 2) in vec3 aTangentToModelSpaceOuterRotor;
 layout (location = 0) in vec3 aPos;
 layout (location = 1) in vec2 aUV;
 layout (location = 2) in vec3 aTangentToModelSpaceCayleyRotor;
+layout (location = 3) in float aTangentHandedness;
 #endif
 
 #ifdef QROTOR_ATTRIBUTES
@@ -79,6 +92,7 @@ layout (location = 2) in vec3 aTangentToModelSpaceCayleyRotor;
 layout (location = 0) in vec3 aPos;
 layout (location = 1) in vec2 aUV;
 layout (location = 2) in vec3 aTangentToModelSpaceQTang;
+layout (location = 3) in float aTangentHandedness;
 #endif
 
 #ifdef MOTOR_ATTRIBUTES
@@ -87,6 +101,7 @@ layout (location = 0) in vec3 aPos;
 layout (location = 1) in vec2 aUV;
 layout (location = 2) in vec4 aTangentToModelSpaceMotor1;
 layout (location = 3) in vec4 aTangentToModelSpaceMotor2;
+layout (location = 4) in float aTangentHandedness;
 #endif
 
 #ifdef OUTER_MOTOR_ATTRIBUTES
@@ -95,6 +110,7 @@ layout (location = 0) in vec3 aPos;
 layout (location = 1) in vec2 aUV;
 layout (location = 2) in vec3 aTangentToModelSpaceOuterEBivector;
 layout (location = 3) in vec3 aTangentToModelSpaceOuterVBivector;
+layout (location = 4) in float aTangentHandedness;
 #endif
 
 #ifdef CAYLEY_MOTOR_ATTRIBUTES
@@ -103,6 +119,73 @@ layout (location = 0) in vec3 aPos;
 layout (location = 1) in vec2 aUV;
 layout (location = 2) in vec3 aTangentToModelSpaceCayleyEBivector;
 layout (location = 3) in vec3 aTangentToModelSpaceCayleyVBivector;
+layout (location = 4) in float aTangentHandedness;
+#endif
+
+#ifdef LOG_MOTOR_ATTRIBUTES
+#undef DEFAULT_ATTRIBUTES
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec2 aUV;
+layout (location = 2) in vec3 aTangentToModelSpaceLogEBivector;
+layout (location = 3) in vec3 aTangentToModelSpaceLogVBivector;
+layout (location = 4) in float aTangentHandedness;
+#endif
+
+#ifdef SKINNED_MOTOR_ATTRIBUTES
+#undef DEFAULT_ATTRIBUTES
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec2 aUV;
+layout (location = 2) in vec4 aTangentToModelSpaceMotor1;
+layout (location = 3) in vec4 aTangentToModelSpaceMotor2;
+layout (location = 4) in float aTangentHandedness;
+layout (location = 5) in vec4 aJoints;
+layout (location = 6) in vec4 aWeights;
+#endif
+
+#ifdef SKINNED_MATRIX_ATTRIBUTES
+#undef DEFAULT_ATTRIBUTES
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec2 aUV;
+layout (location = 2) in vec3 aNormal;
+layout (location = 3) in vec3 aTangent;
+layout (location = 4) in vec3 aBiTangent;
+layout (location = 5) in vec4 aJoints;
+layout (location = 6) in vec4 aWeights;
+#endif
+
+#ifdef ROTOR16_ATTRIBUTES
+#undef DEFAULT_ATTRIBUTES
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec2 aUV;
+layout (location = 2) in vec4 aTangentToModelSpaceRotor;
+layout (location = 3) in float aTangentHandedness;
+#endif
+
+#ifdef ROTOR8_ATTRIBUTES
+#undef DEFAULT_ATTRIBUTES
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec2 aUV;
+layout (location = 2) in vec4 aTangentToModelSpaceRotor;
+layout (location = 3) in float aTangentHandedness;
+#endif
+
+#ifdef QROTOR_PACKED_ATTRIBUTES
+#undef DEFAULT_ATTRIBUTES
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec2 aUV;
+layout (location = 2) in vec4 aTangentToModelSpaceQTangAndHandedness;
+#define aTangentToModelSpaceQTang aTangentToModelSpaceQTangAndHandedness.xyz
+#define aTangentHandedness aTangentToModelSpaceQTangAndHandedness.w
+#endif
+
+#ifdef MOTOR_PACKED_ATTRIBUTES
+#undef DEFAULT_ATTRIBUTES
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec2 aUV;
+layout (location = 2) in vec4 aTangentToModelSpaceRotor;
+layout (location = 3) in vec4 aTangentToModelSpaceTranslationAndHandedness;
+#define aTangentHandedness aTangentToModelSpaceTranslationAndHandedness.w
+#define PACKED_MOTOR
 #endif
 
 #ifdef DEFAULT_ATTRIBUTES
@@ -117,18 +200,55 @@ layout (location = 6) in vec4 aTangentToModelSpaceMotor1;
 layout (location = 7) in vec4 aTangentToModelSpaceMotor2;
 layout (location = 8) in vec3 aTangentToModelSpaceOuterEBivector;
 layout (location = 9) in vec3 aTangentToModelSpaceOuterVBivector;
-layout (location = 10) in vec3 aTangentToModelSpaceOuterRotor;
-layout (location = 11) in vec3 aTangentToModelSpaceQTang;
-layout (location = 12) in vec3 aTangentToModelSpaceCayleyEBivector;
-layout (location = 13) in vec3 aTangentToModelSpaceCayleyVBivector;
-layout (location = 14) in vec3 aTangentToModelSpaceCayleyRotor;
+layout (location = 10) in vec3 aTangentToModelSpaceLogEBivector;
+layout (location = 11) in vec3 aTangentToModelSpaceLogVBivector;
+layout (location = 12) in vec3 aTangentToModelSpaceQTang;
+layout (location = 13) in vec3 aTangentToModelSpaceCayleyEBivector;
+layout (location = 14) in vec3 aTangentToModelSpaceCayleyVBivector;
+layout (location = 15) in float aTangentHandedness;
+#define aTangentToModelSpaceOuterRotor aTangentToModelSpaceOuterEBivector
+#define aTangentToModelSpaceCayleyRotor aTangentToModelSpaceCayleyEBivector
+#endif
+// END GENERATED ATTRIBUTES
+
+// The transform of the instance, in the model space, see src/instancing.rs.
+// MODEL_MATRIX and NORMAL_MATRIX include it, the motor and rotor encodings
+// compose INSTANCE_MOTOR or INSTANCE_ROTOR with the tangent frame. Only the
+// vertex stage can use these.
+#ifdef INSTANCED
+#define MAX_INSTANCES 128
+
+struct Instance {
+	vec4 motor[2];
+	mat4 matrix;
+};
+
+layout (std140) uniform Instances {
+	Instance[MAX_INSTANCES] instances;
+} instancing;
+
+#define INSTANCE instancing.instances[gl_InstanceID]
+#define INSTANCE_MOTOR ppga_motor(INSTANCE.motor[0], INSTANCE.motor[1])
+#define INSTANCE_ROTOR ppga_rotor(INSTANCE.motor[0])
+#define MODEL_MATRIX (uModel * INSTANCE.matrix)
+#define NORMAL_MATRIX (mat3(uNormalMatrix) * mat3(INSTANCE.matrix))
+#else
+#define MODEL_MATRIX uModel
+#define NORMAL_MATRIX mat3(uNormalMatrix)
 #endif
 
 #ifdef TANGENT_MOTOR
 #undef TANGENT_MOTOR
+#ifdef PACKED_MOTOR
+ppga_motor aTangentToModelSpaceMotor() {
+	return ppga_motor_from_rotor_translation(ppga_rotor(aTangentToModelSpaceRotor),
+											 aTangentToModelSpaceTranslationAndHandedness.xyz);
+}
+#else
 ppga_motor aTangentToModelSpaceMotor() {
 	return ppga_motor(aTangentToModelSpaceMotor1, aTangentToModelSpaceMotor2);
 }
+#endif
 #endif 
 
 #endif // GUARD
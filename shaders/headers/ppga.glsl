@@ -33,6 +33,21 @@ vec3 ppga_apply_rotor_to_direction(ppga_rotor r, vec3 d) {
     return res;
 }
 
+// The rotors and motors always describe a right handed tangent frame. Mirrored
+// uv islands have a bitangent that points the other way, which is the same as
+// flipping the y-axis of tangent space before applying the rotor.
+vec3 ppga_mirror_tangent_space(vec3 d, float handedness) {
+    return vec3(d.x, handedness < 0.0 ? -d.y : d.y, d.z);
+}
+
+// The tangent to world matrix of a rotor, with the bitangent mirrored for
+// a negative handedness, for interpolating the frame instead of the rotor.
+mat3 ppga_rotor_to_matrix(ppga_rotor r, float handedness) {
+    return mat3(ppga_apply_rotor_to_direction(r, vec3(1.0, 0.0, 0.0)),
+                ppga_apply_rotor_to_direction(r, vec3(0.0, handedness < 0.0 ? -1.0 : 1.0, 0.0)),
+                ppga_apply_rotor_to_direction(r, vec3(0.0, 0.0, 1.0)));
+}
+
 vec3 ppga_apply_motor_to_direction(ppga_motor m, vec3 dir) {
     ppga_rotor r = ppga_rotor(m.p1);
     return ppga_apply_rotor_to_direction(r, dir);
@@ -46,28 +61,49 @@ vec3 ppga_apply_motor_to_origin(ppga_motor m) {
     return 2.0 * res;
 }
 
-// Not properly tested yet
+// The motor that first applies the rotor and then translates by t.
+ppga_motor ppga_motor_from_rotor_translation(ppga_rotor r, vec3 t) {
+    vec3 tau = 0.5 * t;
+    return ppga_motor(r.p1, vec4(dot(tau, r.p1.yzw), r.p1.x * tau + cross(r.p1.yzw, tau)));
+}
+
+#ifdef KLEIN_GUARD
+// Composition of two transformations, b is applied first and then a.
+ppga_motor ppga_mul(ppga_motor a, ppga_motor b) {
+    kln_motor c = kln_mul(kln_motor(a.p1, a.p2), kln_motor(b.p1, b.p2));
+    return ppga_motor(c.p1, c.p2);
+}
+
+ppga_rotor ppga_mul(ppga_rotor a, ppga_rotor b) {
+    kln_rotor c = kln_mul(kln_rotor(a.p1), kln_rotor(b.p1));
+    return ppga_rotor(c.p1);
+}
+#endif
+
+// Exponential of the bivector with euclidean part eucl (e23, e31, e12) and
+// vanishing part vanish (e01, e02, e03). Below a small angle the terms that
+// divide by the angle are replaced by their Taylor expansions.
 ppga_motor ppga_exp(vec3 eucl, vec3 vanish) {
-    float bdb = dot(eucl, eucl);
-    if (bdb < 0.01)
-        return ppga_motor(vec4(1, eucl), vec4(0, vanish));
-    float u = sqrt(bdb);
-    float v = -dot(eucl, vanish); // meet operation
-    float cu = cos(u);
-    float su = sin(u);
-    float uinv = 1 / u;
-    float suu = su * uinv;
-    float cuvu = -cu * v * uinv;
-    float suvu2 = -su * v / bdb;
-
-    return ppga_motor(vec4(cu,
-                           eucl.x * cuvu + eucl.x * suvu2 + vanish.x * suu,
-                           eucl.y * cuvu + eucl.y * suvu2 + vanish.y * suu,
-                           eucl.z * cuvu + eucl.z * suvu2 + vanish.z * suu),
-                      vec4(-su * v,
-                           eucl.x * suu,
-                           eucl.y * suu,
-                           eucl.z * suu));
+    float uu = dot(eucl, eucl);
+    float u = sqrt(uu);
+    float ab = dot(eucl, vanish);
+    float sinc, f;
+    if (uu < 1e-3) {
+        sinc = 1.0 - uu / 6.0;
+        f = -1.0 / 3.0 + uu / 30.0;
+    } else {
+        sinc = sin(u) / u;
+        f = (cos(u) - sinc) / uu;
+    }
+    return ppga_motor(vec4(cos(u), sinc * eucl),
+                      vec4(ab * sinc, sinc * vanish + ab * f * eucl));
+}
+
+ppga_rotor ppga_exp(vec3 eucl) {
+    float uu = dot(eucl, eucl);
+    float u = sqrt(uu);
+    float sinc = uu < 1e-3 ? 1.0 - uu / 6.0 : sin(u) / u;
+    return ppga_rotor(vec4(cos(u), sinc * eucl));
 }
 
 ppga_motor ppga_cayley_exp(vec3 eucl, vec3 vanish) {
@@ -0,0 +1,44 @@
+#ifndef SKINNING_GLSL
+#define SKINNING_GLSL
+
+// The bones of the skeleton, see src/skinning.rs. Include after ppga.glsl.
+
+#define MAX_BONES 32
+
+// p1 and p2 of the motor of every bone after each other.
+uniform vec4 uBoneMotors[2 * MAX_BONES];
+uniform mat4 uBoneMatrices[MAX_BONES];
+
+// Dual quaternion skinning, the weighted sum of the bone motors. A motor
+// and its negation are the same transformation, so every motor is taken
+// on the side of the first before adding, which keeps the blend on the
+// shortest path. The sum is no longer a rigid motor, so it is fully
+// normalized: the rotor part to unit length and the translation part made
+// orthogonal to it (p1.x * p2.x == dot(p1.yzw, p2.yzw)).
+ppga_motor skin_motor(vec4 joints, vec4 weights) {
+	ivec4 j = ivec4(joints);
+	vec4 first = uBoneMotors[2 * j.x];
+	vec4 p1 = vec4(0.0);
+	vec4 p2 = vec4(0.0);
+	for (int i = 0; i < 4; i++) {
+		vec4 b1 = uBoneMotors[2 * j[i]];
+		vec4 b2 = uBoneMotors[2 * j[i] + 1];
+		float w = dot(first, b1) < 0.0 ? -weights[i] : weights[i];
+		p1 += w * b1;
+		p2 += w * b2;
+	}
+	vec4 p1Reflected = p1 * vec4(1, -1, -1, -1);
+	float squaredNorm = dot(p1, p1);
+	float s = inversesqrt(squaredNorm);
+	float t = dot(p1Reflected, p2) * s / squaredNorm;
+	return ppga_motor(p1 * s, p2 * s - p1Reflected * t);
+}
+
+// Linear blend skinning, the weighted sum of the bone matrices.
+mat4 skin_matrix(vec4 joints, vec4 weights) {
+	ivec4 j = ivec4(joints);
+	return weights.x * uBoneMatrices[j.x] + weights.y * uBoneMatrices[j.y] +
+		   weights.z * uBoneMatrices[j.z] + weights.w * uBoneMatrices[j.w];
+}
+
+#endif
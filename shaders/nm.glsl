@@ -5,8 +5,6 @@
 #define DEFAULT_ATTRIBUTES
 #include headers/app.glsl
 
-uniform mat4 uModel;
-
 out struct {
 	vec3 normal;
 	vec3 fragPosition;
@@ -15,7 +13,7 @@ out struct {
 } vs;
 
 void main() {
-	gl_Position = app.viewProjection * uModel * vec4(aPos, 1.0);
+	gl_Position = app.viewProjection * MODEL_MATRIX * vec4(aPos, 1.0);
 
 	vec3 biTangent = cross(aNormal, aTangent);
 
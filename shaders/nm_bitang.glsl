@@ -5,19 +5,39 @@
 #define DEFAULT_ATTRIBUTES
 #include headers/app.glsl
 
+// Interpolates the matrix unless per fragment is defined, see shaders::DecodePlacement.
+// There is nothing to decode in between, so hybrid is the same as per vertex.
+#if !defined(DECODE_PER_FRAGMENT)
+#define DECODE_PER_VERTEX
+#endif
+
 out struct {
 	vec3 fragPosition;
 	vec2 UV;
+#if defined(DECODE_PER_VERTEX)
 	mat3 tangentToWorldSpace;
+#else
+	vec3 normal;
+	vec3 tangent;
+	float tangentHandedness;
+#endif
 } vs;
 
 void main() {
-	gl_Position = app.viewProjection * uModel * vec4(aPos, 1.0);
+	vec4 worldPos = MODEL_MATRIX * vec4(aPos, 1.0);
+	gl_Position = app.viewProjection * worldPos;
 
-	vec3 biTangent = cross(aNormal, aTangent);
+#if defined(DECODE_PER_VERTEX)
+	vec3 biTangent = cross(aNormal, aTangent) * aTangentHandedness;
 
-	vs.tangentToWorldSpace = mat3(aTangent, biTangent, aNormal); // Model transform must still be added
-	vs.fragPosition = aPos;
+	mat3 model = mat3(MODEL_MATRIX);
+	vs.tangentToWorldSpace = mat3(model * aTangent, model * biTangent, NORMAL_MATRIX * aNormal);
+#else
+	vs.normal = NORMAL_MATRIX * aNormal;
+	vs.tangent = mat3(MODEL_MATRIX) * aTangent;
+	vs.tangentHandedness = aTangentHandedness;
+#endif
+	vs.fragPosition = worldPos.xyz;
 	vs.UV = aUV;
 }
 
@@ -28,12 +48,24 @@ void main() {
 #include headers/phong.glsl
 #include headers/app.glsl
 
+// Interpolates the matrix unless per fragment is defined, see shaders::DecodePlacement.
+// There is nothing to decode in between, so hybrid is the same as per vertex.
+#if !defined(DECODE_PER_FRAGMENT)
+#define DECODE_PER_VERTEX
+#endif
+
 uniform sampler2D uNormalMap;
 
 in struct {
 	vec3 fragPosition;
 	vec2 UV;
+#if defined(DECODE_PER_VERTEX)
 	mat3 tangentToWorldSpace;
+#else
+	vec3 normal;
+	vec3 tangent;
+	float tangentHandedness;
+#endif
 } vs;
 
 out vec4 oFragColor;
@@ -41,7 +73,12 @@ out vec4 oFragColor;
 void main() {
 	vec4 normalM = texture(uNormalMap, vs.UV);
 	vec3 normal = normalM.xyz * 2. - 1.;
+#if defined(DECODE_PER_VERTEX)
 	normal = normalize(vs.tangentToWorldSpace * normal);
+#else
+	vec3 biTangent = cross(vs.normal, vs.tangent) * vs.tangentHandedness;
+	normal = normalize(mat3(vs.tangent, biTangent, vs.normal) * normal);
+#endif
 
 	vec3 lightDir = normalize(vs.fragPosition - app.pointLights[0].position);
 	vec3 eyeDir = normalize(app.eyePosition - vs.fragPosition);
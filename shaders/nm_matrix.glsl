@@ -12,10 +12,12 @@ out struct {
 } vs;
 
 void main() {
-	gl_Position = app.viewProjection * uModel * vec4(aPos, 1.0);
+	vec4 worldPos = MODEL_MATRIX * vec4(aPos, 1.0);
+	gl_Position = app.viewProjection * worldPos;
 
-	vs.tangentToWorldSpace = mat3(aTangent, aBiTangent, aNormal); // Model transform must still be added
-	vs.fragPosition = aPos;
+	mat3 model = mat3(MODEL_MATRIX);
+	vs.tangentToWorldSpace = mat3(model * aTangent, model * aBiTangent, NORMAL_MATRIX * aNormal);
+	vs.fragPosition = worldPos.xyz;
 	vs.UV = aUV;
 }
 
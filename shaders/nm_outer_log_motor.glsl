@@ -9,25 +9,56 @@
 #define DEFAULT_ATTRIBUTES
 #include headers/app.glsl
 
+// Exponentiates per fragment unless another placement is defined, see shaders::DecodePlacement.
+#if !defined(DECODE_PER_VERTEX) && !defined(DECODE_HYBRID)
+#define DECODE_PER_FRAGMENT
+#endif
+
 out struct {
 	vec3 fragPosition;
 	vec2 UV;
-	vec3 tangentToWorldSpaceEBivector;
-	vec3 tangentToWorldSpaceVBivector;
-	// ppga_motor tangentToWorldSpaceMotor;
+#if defined(DECODE_PER_VERTEX)
+	mat3 tangentToWorldSpace;
+#elif defined(DECODE_HYBRID)
+	ppga_motor tangentToWorldSpaceMotor;
+	float tangentHandedness;
+#else
+	vec3 tangentToModelSpaceEBivector;
+	vec3 tangentToModelSpaceVBivector;
+	float tangentHandedness;
+#ifdef INSTANCED
+	ppga_motor instanceMotor;
+#endif
+#endif
 } vs;
 
 void main() {
-	ppga_motor tangentToWorldSpaceMotor = ppga_outer_exp(aTangentToModelSpaceOuterEBivector, 
-												   		 aTangentToModelSpaceOuterVBivector);
+	ppga_motor modelToWorldSpaceMotor = ppga_motor(uModelMotorP1, uModelMotorP2);
+	ppga_motor tangentToModelSpaceMotor = ppga_outer_exp(aTangentToModelSpaceOuterEBivector,
+														 aTangentToModelSpaceOuterVBivector);
+#ifdef INSTANCED
+	tangentToModelSpaceMotor = ppga_mul(INSTANCE_MOTOR, tangentToModelSpaceMotor);
+#endif
+	ppga_motor tangentToWorldSpaceMotor = ppga_mul(modelToWorldSpaceMotor, tangentToModelSpaceMotor);
 	vec3 pos = ppga_apply_motor_to_origin(tangentToWorldSpaceMotor);
-	gl_Position = app.viewProjection * uModel * vec4(pos, 1.0);
+	gl_Position = app.viewProjection * vec4(pos, 1.0);
 
 	vs.fragPosition = pos;
 	vs.UV = aUV;
-	// vs.tangentToWorldSpaceMotor = tangentToWorldSpaceMotor; // Model tranform not yet applied
-	vs.tangentToWorldSpaceEBivector = aTangentToModelSpaceOuterEBivector;
-	vs.tangentToWorldSpaceVBivector = aTangentToModelSpaceOuterVBivector;
+#if defined(DECODE_PER_VERTEX)
+	vs.tangentToWorldSpace = ppga_rotor_to_matrix(ppga_rotor(tangentToWorldSpaceMotor.p1),
+												  aTangentHandedness);
+#elif defined(DECODE_HYBRID)
+	vs.tangentToWorldSpaceMotor = tangentToWorldSpaceMotor;
+	vs.tangentHandedness = aTangentHandedness;
+#else
+	vs.tangentToModelSpaceEBivector = aTangentToModelSpaceOuterEBivector;
+	vs.tangentToModelSpaceVBivector = aTangentToModelSpaceOuterVBivector;
+	vs.tangentHandedness = aTangentHandedness;
+#ifdef INSTANCED
+	vs.instanceMotor = INSTANCE_MOTOR;
+#endif
+#endif
 }
 
 #type fragment
@@ -39,14 +70,29 @@ void main() {
 #include headers/klein.glsl
 #include headers/ppga.glsl
 
+// Exponentiates per fragment unless another placement is defined, see shaders::DecodePlacement.
+#if !defined(DECODE_PER_VERTEX) && !defined(DECODE_HYBRID)
+#define DECODE_PER_FRAGMENT
+#endif
+
 uniform sampler2D uNormalMap;
 
 in struct {
 	vec3 fragPosition;
 	vec2 UV;
-	vec3 tangentToWorldSpaceEBivector;
-	vec3 tangentToWorldSpaceVBivector;
-	// ppga_motor tangentToWorldSpaceMotor;
+#if defined(DECODE_PER_VERTEX)
+	mat3 tangentToWorldSpace;
+#elif defined(DECODE_HYBRID)
+	ppga_motor tangentToWorldSpaceMotor;
+	float tangentHandedness;
+#else
+	vec3 tangentToModelSpaceEBivector;
+	vec3 tangentToModelSpaceVBivector;
+	float tangentHandedness;
+#ifdef INSTANCED
+	ppga_motor instanceMotor;
+#endif
+#endif
 } vs;
 
 out vec4 oFragColor;
@@ -54,11 +100,24 @@ out vec4 oFragColor;
 void main() {
 	vec4 normalM = texture(uNormalMap, vs.UV);
 	vec3 normal = normalM.xyz * 2. - 1.;
-
-	ppga_motor tangentToWorldSpaceMotor = ppga_outer_exp(vs.tangentToWorldSpaceEBivector,
-														 vs.tangentToWorldSpaceVBivector);
-	
+#if defined(DECODE_PER_VERTEX)
+	normal = vs.tangentToWorldSpace * normal;
+#else
+	normal = ppga_mirror_tangent_space(normal, vs.tangentHandedness);
+
+#if defined(DECODE_HYBRID)
+	ppga_motor tangentToWorldSpaceMotor = vs.tangentToWorldSpaceMotor;
+#else
+	ppga_motor tangentToModelSpaceMotor = ppga_outer_exp(vs.tangentToModelSpaceEBivector,
+														 vs.tangentToModelSpaceVBivector);
+#ifdef INSTANCED
+	tangentToModelSpaceMotor = ppga_mul(vs.instanceMotor, tangentToModelSpaceMotor);
+#endif
+	ppga_motor tangentToWorldSpaceMotor = ppga_mul(ppga_motor(uModelMotorP1, uModelMotorP2),
+												   tangentToModelSpaceMotor);
+#endif
 	normal = ppga_apply_motor_to_direction(tangentToWorldSpaceMotor, normal);
+#endif
 	normal = normalize(normal);
 
 	vec3 lightDir = normalize(vs.fragPosition - app.pointLights[0].position);
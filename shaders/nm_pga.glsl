@@ -5,8 +5,6 @@
 #define DEFAULT_ATTRIBUTES
 #include headers/app.glsl
 
-uniform mat4 uModel;
-
 out struct {
 	vec3 fragPosition;
 	vec2 UV;
@@ -14,11 +12,11 @@ out struct {
 } vs;
 
 void main() {
-	gl_Position = app.viewProjection * uModel * vec4(aPos, 1.0);
+	gl_Position = app.viewProjection * MODEL_MATRIX * vec4(aPos, 1.0);
 
 	vs.fragPosition = aPos;
 	vs.UV = aUV;
-	vs.tangentToWorldSpaceRotor = aTangentToWorldSpaceRotor;
+	vs.tangentToWorldSpaceRotor = aTangentToModelSpaceRotor;
 }
 
 #type fragment
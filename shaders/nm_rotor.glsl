@@ -9,18 +9,53 @@
 #define DEFAULT_ATTRIBUTES
 #include headers/app.glsl
 
+// Interpolates the rotor unless another placement is defined, see shaders::DecodePlacement.
+#if !defined(DECODE_PER_VERTEX) && !defined(DECODE_PER_FRAGMENT)
+#define DECODE_HYBRID
+#endif
+
 out struct {
 	vec3 fragPosition;
 	vec2 UV;
+#if defined(DECODE_PER_VERTEX)
+	mat3 tangentToWorldSpace;
+#elif defined(DECODE_HYBRID)
 	ppga_rotor tangentToWorldSpaceRotor;
+	float tangentHandedness;
+#else
+	vec4 tangentToModelSpaceRotor;
+	float tangentHandedness;
+#ifdef INSTANCED
+	vec4 instanceRotor;
+#endif
+#endif
 } vs;
 
 void main() {
-	gl_Position = app.viewProjection * uModel * vec4(aPos, 1.0);
+	vec4 worldPos = MODEL_MATRIX * vec4(aPos, 1.0);
+	gl_Position = app.viewProjection * worldPos;
 
-	vs.fragPosition = aPos;
+	vs.fragPosition = worldPos.xyz;
 	vs.UV = aUV;
-	vs.tangentToWorldSpaceRotor = ppga_rotor(aTangentToModelSpaceRotor); // Model transform not applied
+#if defined(DECODE_PER_FRAGMENT)
+	vs.tangentToModelSpaceRotor = aTangentToModelSpaceRotor;
+	vs.tangentHandedness = aTangentHandedness;
+#ifdef INSTANCED
+	vs.instanceRotor = INSTANCE.motor[0];
+#endif
+#else
+	ppga_rotor tangentToModel = ppga_rotor(aTangentToModelSpaceRotor);
+#ifdef INSTANCED
+	tangentToModel = ppga_mul(INSTANCE_ROTOR, tangentToModel);
+#endif
+	ppga_rotor tangentToWorld = ppga_mul(ppga_rotor(uModelMotorP1), tangentToModel);
+#if defined(DECODE_PER_VERTEX)
+	vs.tangentToWorldSpace = ppga_rotor_to_matrix(tangentToWorld, aTangentHandedness);
+#else
+	vs.tangentToWorldSpaceRotor = tangentToWorld;
+	vs.tangentHandedness = aTangentHandedness;
+#endif
+#endif
 }
 
 #type fragment
@@ -32,12 +67,28 @@ void main() {
 #include headers/klein.glsl
 #include headers/ppga.glsl
 
+// Interpolates the rotor unless another placement is defined, see shaders::DecodePlacement.
+#if !defined(DECODE_PER_VERTEX) && !defined(DECODE_PER_FRAGMENT)
+#define DECODE_HYBRID
+#endif
+
 uniform sampler2D uNormalMap;
 
 in struct {
 	vec3 fragPosition;
 	vec2 UV;
+#if defined(DECODE_PER_VERTEX)
+	mat3 tangentToWorldSpace;
+#elif defined(DECODE_HYBRID)
 	ppga_rotor tangentToWorldSpaceRotor;
+	float tangentHandedness;
+#else
+	vec4 tangentToModelSpaceRotor;
+	float tangentHandedness;
+#ifdef INSTANCED
+	vec4 instanceRotor;
+#endif
+#endif
 } vs;
 
 out vec4 oFragColor;
@@ -45,8 +96,22 @@ out vec4 oFragColor;
 void main() {
 	vec4 normalM = texture(uNormalMap, vs.UV);
 	vec3 normal = normalM.xyz * 2. - 1.;
-	
-	normal = ppga_apply_rotor_to_direction(vs.tangentToWorldSpaceRotor, normal);
+#if defined(DECODE_PER_VERTEX)
+	normal = vs.tangentToWorldSpace * normal;
+#else
+	normal = ppga_mirror_tangent_space(normal, vs.tangentHandedness);
+
+#if defined(DECODE_HYBRID)
+	ppga_rotor tangentToWorld = vs.tangentToWorldSpaceRotor;
+#else
+	ppga_rotor tangentToModel = ppga_rotor(vs.tangentToModelSpaceRotor);
+#ifdef INSTANCED
+	tangentToModel = ppga_mul(ppga_rotor(vs.instanceRotor), tangentToModel);
+#endif
+	ppga_rotor tangentToWorld = ppga_mul(ppga_rotor(uModelMotorP1), tangentToModel);
+#endif
+	normal = ppga_apply_rotor_to_direction(tangentToWorld, normal);
+#endif
 	normal = normalize(normal);
 
 	vec3 lightDir = normalize(vs.fragPosition - app.pointLights[0].position);
@@ -0,0 +1,60 @@
+#type vertex
+
+#version 330 core
+
+// Only drawn with the skinned layout, it has the joints and weights.
+#ifndef SKINNED_MATRIX_ATTRIBUTES
+#define SKINNED_MATRIX_ATTRIBUTES
+#endif
+#include headers/app.glsl
+#include headers/ppga.glsl
+#include headers/skinning.glsl
+
+out struct {
+	vec3 fragPosition;
+	vec2 UV;
+	mat3 tangentToWorldSpace;
+} vs;
+
+void main() {
+	mat4 skin = skin_matrix(aJoints, aWeights);
+	vec4 worldPos = MODEL_MATRIX * skin * vec4(aPos, 1.0);
+	gl_Position = app.viewProjection * worldPos;
+
+	// The blended matrix is taken to be rigid enough for the normal too,
+	// as is usual for linear blend skinning.
+	mat3 skinnedModel = mat3(MODEL_MATRIX) * mat3(skin);
+	vs.tangentToWorldSpace = mat3(skinnedModel * aTangent, skinnedModel * aBiTangent,
+								  NORMAL_MATRIX * mat3(skin) * aNormal);
+	vs.fragPosition = worldPos.xyz;
+	vs.UV = aUV;
+}
+
+#type fragment
+
+#version 330 core
+
+#include headers/phong.glsl
+#include headers/app.glsl
+
+uniform sampler2D uNormalMap;
+
+in struct {
+	vec3 fragPosition;
+	vec2 UV;
+	mat3 tangentToWorldSpace;
+} vs;
+
+out vec4 oFragColor;
+
+void main() {
+	vec4 normalM = texture(uNormalMap, vs.UV);
+	vec3 normal = normalM.xyz * 2. - 1.;
+	normal = normalize(vs.tangentToWorldSpace * normal);
+
+	vec3 lightDir = normalize(vs.fragPosition - app.pointLights[0].position);
+	vec3 eyeDir = normalize(app.eyePosition - vs.fragPosition);
+
+	oFragColor = phong(normal, uMaterial.albedo, lightDir, eyeDir, app.pointLights[0].color,
+					   uMaterial.reflectiveness, uMaterial.ambient, uMaterial.specular);
+}
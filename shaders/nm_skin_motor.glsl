@@ -0,0 +1,117 @@
+#type vertex
+
+#version 330 core
+
+#include headers/klein.glsl
+#include headers/ppga.glsl
+
+// Only drawn with the skinned layout, it has the joints and weights.
+#define TANGENT_MOTOR
+#ifndef SKINNED_MOTOR_ATTRIBUTES
+#define SKINNED_MOTOR_ATTRIBUTES
+#endif
+#include headers/app.glsl
+#include headers/skinning.glsl
+
+// Interpolates the motor unless another placement is defined, see shaders::DecodePlacement.
+#if !defined(DECODE_PER_VERTEX) && !defined(DECODE_PER_FRAGMENT)
+#define DECODE_HYBRID
+#endif
+
+out struct {
+	vec3 fragPosition;
+	vec2 UV;
+#if defined(DECODE_PER_VERTEX)
+	mat3 tangentToWorldSpace;
+#elif defined(DECODE_HYBRID)
+	ppga_motor tangentToWorldSpaceMotor;
+	float tangentHandedness;
+#else
+	ppga_motor tangentToModelSpaceMotor;
+	float tangentHandedness;
+#endif
+} vs;
+
+void main() {
+	ppga_motor modelToWorldSpaceMotor = ppga_motor(uModelMotorP1, uModelMotorP2);
+	// The blended bone motor moves the tangent frame with the vertex, so
+	// the skinned frame is decoded like the frame of a static mesh.
+	ppga_motor skinMotor = skin_motor(aJoints, aWeights);
+	ppga_motor tangentToModelSpaceMotor = ppga_mul(skinMotor, aTangentToModelSpaceMotor());
+#ifdef INSTANCED
+	tangentToModelSpaceMotor = ppga_mul(INSTANCE_MOTOR, tangentToModelSpaceMotor);
+#endif
+	ppga_motor tangentToWorldSpaceMotor = ppga_mul(modelToWorldSpaceMotor, tangentToModelSpaceMotor);
+	vec3 pos = ppga_apply_motor_to_origin(tangentToWorldSpaceMotor);
+	gl_Position = app.viewProjection * vec4(pos, 1.0);
+
+	vs.fragPosition = pos;
+	vs.UV = aUV;
+#if defined(DECODE_PER_VERTEX)
+	vs.tangentToWorldSpace = ppga_rotor_to_matrix(ppga_rotor(tangentToWorldSpaceMotor.p1),
+												  aTangentHandedness);
+#elif defined(DECODE_HYBRID)
+	vs.tangentToWorldSpaceMotor = tangentToWorldSpaceMotor;
+	vs.tangentHandedness = aTangentHandedness;
+#else
+	vs.tangentToModelSpaceMotor = tangentToModelSpaceMotor;
+	vs.tangentHandedness = aTangentHandedness;
+#endif
+}
+
+#type fragment
+
+#version 330 core
+
+#include headers/phong.glsl
+#include headers/app.glsl
+#include headers/klein.glsl
+#include headers/ppga.glsl
+
+// Interpolates the motor unless another placement is defined, see shaders::DecodePlacement.
+#if !defined(DECODE_PER_VERTEX) && !defined(DECODE_PER_FRAGMENT)
+#define DECODE_HYBRID
+#endif
+
+uniform sampler2D uNormalMap;
+
+in struct {
+	vec3 fragPosition;
+	vec2 UV;
+#if defined(DECODE_PER_VERTEX)
+	mat3 tangentToWorldSpace;
+#elif defined(DECODE_HYBRID)
+	ppga_motor tangentToWorldSpaceMotor;
+	float tangentHandedness;
+#else
+	ppga_motor tangentToModelSpaceMotor;
+	float tangentHandedness;
+#endif
+} vs;
+
+out vec4 oFragColor;
+
+void main() {
+	vec4 normalM = texture(uNormalMap, vs.UV);
+	vec3 normal = normalM.xyz * 2. - 1.;
+#if defined(DECODE_PER_VERTEX)
+	normal = vs.tangentToWorldSpace * normal;
+#else
+	normal = ppga_mirror_tangent_space(normal, vs.tangentHandedness);
+
+#if defined(DECODE_HYBRID)
+	ppga_motor tangentToWorldSpaceMotor = vs.tangentToWorldSpaceMotor;
+#else
+	ppga_motor tangentToWorldSpaceMotor = ppga_mul(ppga_motor(uModelMotorP1, uModelMotorP2),
+												   vs.tangentToModelSpaceMotor);
+#endif
+	normal = ppga_apply_motor_to_direction(tangentToWorldSpaceMotor, normal);
+#endif
+	normal = normalize(normal);
+
+	vec3 lightDir = normalize(vs.fragPosition - app.pointLights[0].position);
+	vec3 eyeDir = normalize(app.eyePosition - vs.fragPosition);
+
+	oFragColor = phong(normal, uMaterial.albedo, lightDir, eyeDir, app.pointLights[0].color,
+					   uMaterial.reflectiveness, uMaterial.ambient, uMaterial.specular);
+}
@@ -12,12 +12,14 @@ out struct {
 } vs;
 
 void main() {
-	gl_Position = app.viewProjection * uModel * vec4(aPos, 1.0);
+	vec4 worldPos = MODEL_MATRIX * vec4(aPos, 1.0);
+	gl_Position = app.viewProjection * worldPos;
 
-	vec3 biTangent = cross(aNormal, aTangent);
+	vec3 biTangent = cross(aNormal, aTangent) * aTangentHandedness;
 
-	vs.tangentToWorldSpace = mat3(aTangent, biTangent, aNormal);
-	vs.fragPosition = aPos;
+	mat3 model = mat3(MODEL_MATRIX);
+	vs.tangentToWorldSpace = mat3(model * aTangent, model * biTangent, NORMAL_MATRIX * aNormal);
+	vs.fragPosition = worldPos.xyz;
 	vs.UV = aUV;
 }
 
@@ -30,6 +32,7 @@ void main() {
 
 uniform sampler2D uNormalMap;
 uniform sampler2D uAlbedoMap;
+uniform sampler2D uRoughnessMap;
 
 in struct {
 	vec3 fragPosition;
@@ -48,6 +51,9 @@ void main() {
 	vec3 lightDir = normalize(vs.fragPosition - app.pointLights[0].position);
 	vec3 eyeDir = normalize(app.eyePosition - vs.fragPosition);
 
+	// Rough parts of the surface get a weaker highlight.
+	float specular = uMaterial.specular * (1. - texture(uRoughnessMap, vs.UV).g);
+
 	oFragColor = phong(normal, albedo, lightDir, eyeDir, app.pointLights[0].color,
-					   uMaterial.reflectiveness, uMaterial.ambient, uMaterial.specular);
+					   uMaterial.reflectiveness, uMaterial.ambient, specular);
 }
@@ -11,10 +11,11 @@ out struct {
 } vs;
 
 void main() {
-	gl_Position = app.viewProjection * uModel * vec4(aPos, 1.0);
+	vec4 worldPos = MODEL_MATRIX * vec4(aPos, 1.0);
+	gl_Position = app.viewProjection * worldPos;
 
-	vs.fragPosition = aPos;
-	vs.normal = aNormal;
+	vs.fragPosition = worldPos.xyz;
+	vs.normal = NORMAL_MATRIX * aNormal;
 	vs.UV = aUV;
 }
 
@@ -25,6 +26,7 @@ void main() {
 #include headers/app.glsl
 
 uniform sampler2D uAlbedoMap;
+uniform sampler2D uRoughnessMap;
 
 in struct {
 	vec3 normal;
@@ -42,6 +44,9 @@ void main() {
 	vec3 lightDir = normalize(vs.fragPosition - app.pointLights[0].position);
 	vec3 eyeDir = normalize(app.eyePosition - vs.fragPosition);
 
+	// Rough parts of the surface get a weaker highlight.
+	float specular = uMaterial.specular * (1. - texture(uRoughnessMap, vs.UV).g);
+
 	oFragColor = phong(normal, albedo, lightDir, eyeDir, app.pointLights[0].color,
-					   uMaterial.reflectiveness, uMaterial.ambient, uMaterial.specular);
+					   uMaterial.reflectiveness, uMaterial.ambient, specular);
 }
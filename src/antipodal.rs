@@ -0,0 +1,186 @@
+//! A rotor `q` and `-q` describe the same frame, but the rasterizer
+//! interpolates the components linearly. When two vertices of a triangle
+//! carry rotors of opposite sign the interpolated rotor passes through zero
+//! and the decoded normals break down. The functions here pick the signs per
+//! mesh such that every triangle stays in one hemisphere.
+//!
+//! Only the full rotor and motor encodings are touched, the logarithmic
+//! parameterisations are computed from a canonical rotor and do not depend
+//! on the sign that is picked here.
+
+use crate::vertices::All;
+use std::collections::{HashMap, VecDeque};
+
+/// Flips the rotors and motors of the vertices such that neighbouring
+/// vertices are in the same hemisphere. Returns the indices of the faces for
+/// which no consistent choice was found, these can be fixed using
+/// [`split_seams`].
+pub fn align(vertices: &mut [All], faces: &[[u32; 3]]) -> Vec<usize> {
+    let mut neighbours = vec![Vec::new(); vertices.len()];
+    for face in faces.iter() {
+        for corner in 0..3 {
+            let a = face[corner] as usize;
+            let b = face[(corner + 1) % 3] as usize;
+            neighbours[a].push(b);
+            neighbours[b].push(a);
+        }
+    }
+
+    // Breadth first, so that the signs are propagated over the surface
+    // instead of jumping around in index order.
+    let mut visited = vec![false; vertices.len()];
+    let mut queue = VecDeque::new();
+    for start in 0..vertices.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        queue.push_back(start);
+        while let Some(i) = queue.pop_front() {
+            for &n in neighbours[i].iter() {
+                if visited[n] {
+                    continue;
+                }
+                if dot(&vertices[i].rotor, &vertices[n].rotor) < 0. {
+                    flip(&mut vertices[n]);
+                }
+                visited[n] = true;
+                queue.push_back(n);
+            }
+        }
+    }
+
+    faces
+        .iter()
+        .enumerate()
+        .filter(|(_, face)| !is_consistent(vertices, face))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Duplicates the vertices of the given faces that are on the wrong side,
+/// with the opposite sign, and points the faces to the duplicates. After
+/// this every face in `seams` is consistent as far as possible, for
+/// triangles that span more than a hemisphere the best pick is made.
+pub fn split_seams(vertices: &mut Vec<All>, faces: &mut [[u32; 3]], seams: &[usize]) {
+    let mut flipped_copies: HashMap<u32, u32> = HashMap::new();
+    for &f in seams.iter() {
+        let face = faces[f];
+        let rotors = [
+            vertices[face[0] as usize].rotor,
+            vertices[face[1] as usize].rotor,
+            vertices[face[2] as usize].rotor,
+        ];
+
+        // The sign of the first corner is kept, try the four options for
+        // the other two and keep the one with the largest minimal dot.
+        let mut best = (f32::MIN, [false; 3]);
+        for &flip_1 in [false, true].iter() {
+            for &flip_2 in [false, true].iter() {
+                let signs = [1., sign(flip_1), sign(flip_2)];
+                let mut min_dot = f32::MAX;
+                for (a, b) in [(0, 1), (1, 2), (2, 0)].iter() {
+                    let d = signs[*a] * signs[*b] * dot(&rotors[*a], &rotors[*b]);
+                    min_dot = min_dot.min(d);
+                }
+                if min_dot > best.0 {
+                    best = (min_dot, [false, flip_1, flip_2]);
+                }
+            }
+        }
+
+        for corner in 0..3 {
+            if !best.1[corner] {
+                continue;
+            }
+            let original = face[corner];
+            let copy = *flipped_copies.entry(original).or_insert_with(|| {
+                let mut v = vertices[original as usize].clone();
+                flip(&mut v);
+                vertices.push(v);
+                (vertices.len() - 1) as u32
+            });
+            faces[f][corner] = copy;
+        }
+    }
+}
+
+fn is_consistent(vertices: &[All], face: &[u32; 3]) -> bool {
+    let r = |i: usize| &vertices[face[i] as usize].rotor;
+    dot(r(0), r(1)) >= 0. && dot(r(1), r(2)) >= 0. && dot(r(2), r(0)) >= 0.
+}
+
+fn flip(v: &mut All) {
+    v.rotor.iter_mut().for_each(|x| *x = -*x);
+    v.motor
+        .iter_mut()
+        .flat_map(|p| p.iter_mut())
+        .for_each(|x| *x = -*x);
+}
+
+fn dot(a: &[f32; 4], b: &[f32; 4]) -> f32 {
+    a.iter().zip(b.iter()).map(|(a, b)| a * b).sum()
+}
+
+fn sign(flipped: bool) -> f32 {
+    if flipped {
+        -1.
+    } else {
+        1.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vertices::PosUVNormTang;
+
+    /// A closed strip of `n` by 2 vertices around the z axis, with the
+    /// tangent turning once around the normal along the strip. Going around
+    /// takes the rotor to its negative, so no choice of signs is consistent
+    /// everywhere. Some of the rotors start out flipped.
+    fn twisted_ring(n: usize) -> (Vec<All>, Vec<[u32; 3]>) {
+        let mut vertices = Vec::new();
+        for i in 0..n {
+            let angle = std::f32::consts::TAU * i as f32 / n as f32;
+            for z in 0..2 {
+                let mut v = All::from(PosUVNormTang {
+                    position: [angle.cos(), angle.sin(), z as f32],
+                    uv: [i as f32 / n as f32, z as f32],
+                    normal: [0., 0., 1.],
+                    tangent: [angle.cos(), angle.sin(), 0.],
+                    handedness: 1.,
+                });
+                if i % 3 == 0 {
+                    flip(&mut v);
+                }
+                vertices.push(v);
+            }
+        }
+        let mut faces = Vec::new();
+        for i in 0..n {
+            let a = 2 * i as u32;
+            let b = 2 * ((i + 1) % n) as u32;
+            faces.push([a, b, a + 1]);
+            faces.push([b, b + 1, a + 1]);
+        }
+        (vertices, faces)
+    }
+
+    #[test]
+    fn no_edge_has_a_negative_dot_after_aligning() {
+        let (mut vertices, mut faces) = twisted_ring(32);
+        let seams = align(&mut vertices, &faces);
+        assert!(!seams.is_empty());
+        split_seams(&mut vertices, &mut faces, &seams);
+
+        for face in faces.iter() {
+            for corner in 0..3 {
+                let a = &vertices[face[corner] as usize];
+                let b = &vertices[face[(corner + 1) % 3] as usize];
+                assert!(dot(&a.rotor, &b.rotor) >= 0., "{:?}", face);
+                assert!(dot(&a.motor[0], &b.motor[0]) >= 0., "{:?}", face);
+            }
+        }
+    }
+}
@@ -1,8 +1,21 @@
 use crate::cameras::Eye;
 use crate::shaders::Shader;
-use crate::{cameras, imgui_widgets, lights, material, painters, shaders, time, vertices};
-
-/// Simple inspector GUI application for viewing hard-coded graphics.
+use crate::{
+    antipodal, cameras, gltf_loader, hot_reload, imgui_widgets, instancing, lights, material,
+    painters, scene_file, shaders, tangents, techniques, time, vertices,
+};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Texture slots of the maps of a material, they are bound for every
+/// model.
+const ALBEDO_SLOT: u32 = 1;
+const NORMAL_MAP_SLOT: u32 = 2;
+const ROUGHNESS_SLOT: u32 = 3;
+
+/// Simple inspector GUI application for viewing the graphics of a scene
+/// file, see `scene_file`.
 pub struct App {
     window: pgl::window::GlfwWindow,
     uniforms: shaders::AppUniforms, // Uniform buffer accessed by all shaders.
@@ -13,13 +26,13 @@ pub struct App {
     cameras: Cameras,
     renderers: Renderers,
     shaders: Shaders,
-    _textures: Textures, // Never used, but saved so that drop function is not called.
     scene: Scene,
 }
 
 impl App {
-    pub fn new() -> Self {
-        let model_reciever = Self::make_geometry();
+    pub fn new(scene_path: impl AsRef<std::path::Path>) -> Self {
+        let file = scene_file::SceneFile::from_path(scene_path);
+        let model_reciever = Self::make_geometry(&file.meshes);
 
         let window = pgl::window::GlfwWindow::new(1400, 800, "PGA FOR THE WIN");
         pgl::utils::gl::set_default_options();
@@ -30,56 +43,56 @@ impl App {
         let uniforms = shaders::AppUniforms::new();
 
         let state = State {
-            flying_cam: false,
+            flying_cam: file.camera.flying,
             bgcolor: [0., 0., 0.],
-            n_draws: 1,
-            texture: 1,
-            normal_map: 1,
-            shader: 4,
+            n_instances: 1,
+            selected_model: 0,
+            shader: techniques::in_app()
+                .position(|t| t.name == "rotor")
+                .unwrap(),
+            decode_placement: 0,
             wireframe: false,
             model_rotation_x: 0.,
         };
 
+        let camera = &file.camera;
         let cameras = Cameras {
-            inspector: cameras::Inspector::default(),
-            fly: cameras::Flying::new(),
+            inspector: cameras::Inspector::new(camera.distance, camera.longitude, camera.latitude),
+            fly: cameras::Flying::at(camera.position.into()),
         };
 
-        let _textures = Textures {
-            world: pgl::texture::Texture::from_path("imgs/world.jpeg", Default::default()),
-            brick_normals: pgl::texture::Texture::from_path(
-                "imgs/brick_normals.png",
-                Default::default(),
-            ),
-            wall_normals: pgl::texture::Texture::from_path(
-                "imgs/wall_normals.jpeg",
-                Default::default(),
-            ),
-            wall_albedo: pgl::texture::Texture::from_path(
-                "imgs/wall_albedo.jpeg",
-                Default::default(),
-            ),
-        };
-        // Bind textures to opengl texture slots.
-        _textures.brick_normals.bind_to(1).unwrap();
-        _textures.world.bind_to(2).unwrap();
-        _textures.wall_albedo.bind_to(3).unwrap();
-        _textures.wall_normals.bind_to(4).unwrap();
-
-        let shaders = Shaders {
-            bitang_nm: shaders::NormalMapping::new("shaders/nm_bitang.glsl"),
-            matrix_nm: shaders::NormalMapping::new("shaders/nm_matrix.glsl"),
-            rotor_nm: shaders::NormalMapping::new("shaders/nm_rotor.glsl"),
-            motor_nm: shaders::NormalMapping::new("shaders/nm_motor.glsl"),
-            outer_log_motor_nm: shaders::NormalMapping::new("shaders/nm_outer_log_motor.glsl"),
-            outer_log_rotor_nm: shaders::NormalMapping::new("shaders/nm_outer_log_rotor.glsl"),
-            cayley_log_motor_nm: shaders::NormalMapping::new("shaders/nm_cayley_motor.glsl"),
-            cayley_log_rotor_nm: shaders::NormalMapping::new("shaders/nm_cayley_rotor.glsl"),
-            qtang_nm: shaders::NormalMapping::new("shaders/nm_qtang.glsl"),
-            textured: shaders::Textured::new(),
-            flat: shaders::Flat::new(),
-            nm_tex: shaders::NormalAlbedoMapping::new(),
-        };
+        let textures = file
+            .textures
+            .iter()
+            .map(|texture| {
+                let loaded = pgl::texture::Texture::from_path(&texture.path, Default::default());
+                SceneTexture {
+                    name: texture.name.clone(),
+                    kind: texture.kind,
+                    texture: material::TextureHandle::File(Rc::new(loaded)),
+                }
+            })
+            .collect::<Vec<_>>();
+        let materials = file
+            .materials
+            .iter()
+            .map(|m| {
+                let map = |kind: techniques::Texture, name: &Option<String>| {
+                    let name = name.as_ref()?;
+                    let texture = textures.iter().find(|t| t.kind == kind && &t.name == name);
+                    Some(texture.unwrap().texture.clone())
+                };
+                let material = material::Material {
+                    albedo_map: map(techniques::Texture::Albedo, &m.albedo_map),
+                    normal_map: map(techniques::Texture::NormalMap, &m.normal_map),
+                    roughness_map: map(techniques::Texture::Roughness, &m.roughness_map),
+                    ..m.parameters.clone()
+                };
+                (m.name.clone(), material)
+            })
+            .collect();
+
+        let shaders = Shaders::new(state.decode_placement);
 
         let renderers = Renderers {
             imgui: painters::imgui::ImguiRenderer::new(&mut imgui, shaders::Ui::new()),
@@ -91,8 +104,15 @@ impl App {
             transform: glm::Mat4::identity(),
             models: Vec::new(),
             model_reciever,
-            material: material::Material::default(),
-            light: lights::CameraFollowingLight::new(),
+            uploads: HashMap::new(),
+            textures,
+            neutral_maps: NeutralMaps::new(),
+            instances: instancing::InstanceBuffer::new(),
+            materials,
+            light: lights::CameraFollowingLight {
+                with_mouse: file.light.follows_mouse,
+                color: file.light.color,
+            },
         };
 
         Self {
@@ -102,7 +122,6 @@ impl App {
             uniforms,
             cameras,
             state,
-            _textures,
             renderers,
             shaders,
             scene,
@@ -111,11 +130,18 @@ impl App {
     pub fn update(&mut self) {
         // Checking whether a mesh creation thread has finished a mesh.
         while let Ok(mesh) = self.scene.model_reciever.try_recv() {
-            self.scene.models.push(Self::make_model(&mesh));
+            let model = Self::make_model(&mesh, &mut self.scene);
+            self.scene.models.push(model);
         }
 
         imgui_glfw::imgui::impl_glfw::new_frame(&mut self.imgui);
 
+        if self.state.decode_placement != self.shaders.decode_placement {
+            self.shaders
+                .set_decode_placement(self.state.decode_placement);
+        }
+        self.shaders.reload_changed();
+
         self.time.update(pgl::window::GlfwWindow::time());
 
         let main_eye = unsafe { self.main_camera().as_mut().unwrap() };
@@ -123,7 +149,7 @@ impl App {
 
         self.uniforms.update(
             main_eye,
-            &self.window,
+            self.window.aspect(),
             self.scene.light.position(&self.window, main_eye).into(),
             self.scene.light.color,
         );
@@ -142,10 +168,11 @@ impl App {
 
         // UI
         let mut ui = self.imgui.frame();
-        imgui_widgets::material_editor(&mut ui, &mut self.scene.material);
+        imgui_widgets::material_editor(&mut ui, &mut self.state, &mut self.scene);
         imgui_widgets::main_options(&mut ui, &mut self.state, &mut self.scene);
         imgui_widgets::shading(&mut ui, &mut self.state);
         imgui_widgets::performance(&mut ui, &self.time, &self.scene, &self.window, &self.state);
+        imgui_widgets::shader_errors(&mut ui, &self.shaders.errors);
         //let main_camera = unsafe { self.main_camera().as_ref().unwrap() };
         //self.scene.transform =
         //imgui_widgets::imguizmos(&mut ui, self.scene.transform, main_camera, &self.window);
@@ -171,18 +198,39 @@ impl App {
             settings::disable(&[settings::Option::Wireframe]);
         }
 
+        let n_instances = self.state.n_instances as usize;
+        if self.scene.instances.count() != n_instances {
+            let instances = (0..n_instances)
+                .map(|i| {
+                    let offset = i as f32 * 3.0 * -glm::Vec3::z();
+                    instancing::Instance::new(&glm::translation(&offset))
+                })
+                .collect::<Vec<_>>();
+            self.scene.instances.fill(&instances);
+        }
+
         let shader = unsafe { self.set_shading().as_mut().unwrap() };
-        shader.set_material(&self.scene.material);
-        for i in 0..self.state.n_draws {
-            let translation = glm::translation::<f32>(&(i as f32 * 3.0 * -glm::Vec3::z()));
-            shader.set_model(&(translation * self.scene.transform));
-            for model in self.scene.models.iter() {
-                if !model.active {
-                    continue;
+        let neutral = &self.scene.neutral_maps;
+        for model in self.scene.models.iter() {
+            if !model.active {
+                continue;
+            }
+            shader.set_model(&(self.scene.transform * model.transform));
+            let material = &model.material;
+            shader.set_material(material);
+            let maps = [
+                (&material.albedo_map, &neutral.albedo, ALBEDO_SLOT),
+                (&material.normal_map, &neutral.normal_map, NORMAL_MAP_SLOT),
+                (&material.roughness_map, &neutral.roughness, ROUGHNESS_SLOT),
+            ];
+            for (map, neutral, slot) in maps.iter() {
+                match map {
+                    Some(texture) => texture.bind_to(*slot),
+                    None => neutral.bind_to(*slot),
                 }
-                model.vao.bind();
-                pgl::utils::gl::draw(model.n_indices);
             }
+            model.vao.bind();
+            instancing::draw(model.n_indices, n_instances);
         }
         settings::disable(&[settings::Option::Wireframe]);
     }
@@ -203,101 +251,42 @@ impl App {
     /// Sets the shading settings based on the state (changable in GUI)
     /// and returns the active shader.
     fn set_shading(&mut self) -> *mut dyn Shader {
-        let texture_slot = match self.state.texture {
-            0 => 2,
-            1 => 3,
-            _ => unreachable!(),
-        };
-        let normal_map_slot = match self.state.normal_map {
-            0 => 1,
-            1 => 4,
-            _ => unreachable!(),
-        };
-
-        match self.state.shader {
-            0 => {
-                self.shaders.flat.bind();
-                self.shaders
-                    .flat
-                    .set_uniforms(&self.scene.material.albedo.into());
-                &mut self.shaders.flat
-            }
-            1 => {
-                self.shaders.textured.bind();
-                self.shaders.textured.set_uniforms(texture_slot);
-                &mut self.shaders.textured
-            }
-            2 => {
-                self.shaders.matrix_nm.bind();
-                self.shaders.matrix_nm.set_uniforms(normal_map_slot);
-                &mut self.shaders.matrix_nm
-            }
-            3 => {
-                self.shaders.nm_tex.bind();
-                self.shaders
-                    .nm_tex
-                    .set_uniforms(normal_map_slot, texture_slot);
-                &mut self.shaders.nm_tex
-            }
-            4 => {
-                self.shaders.rotor_nm.bind();
-                self.shaders.rotor_nm.set_uniforms(normal_map_slot);
-                &mut self.shaders.rotor_nm
-            }
-            5 => {
-                self.shaders.motor_nm.bind();
-                self.shaders.motor_nm.set_uniforms(normal_map_slot);
-                &mut self.shaders.motor_nm
-            }
-            // 6 => {
-            //     self.shaders.log_motor_nm.bind();
-            //     self.shaders.log_motor_nm.set_uniforms(normal_map_slot);
-            //     &mut self.shaders.log_motor_nm
-            // }
-            6 => {
-                self.shaders.outer_log_motor_nm.bind();
-                self.shaders
-                    .outer_log_motor_nm
-                    .set_uniforms(normal_map_slot);
-                &mut self.shaders.outer_log_motor_nm
-            }
-            7 => {
-                self.shaders.outer_log_rotor_nm.bind();
-                self.shaders
-                    .outer_log_rotor_nm
-                    .set_uniforms(normal_map_slot);
-                &mut self.shaders.outer_log_rotor_nm
-            }
-            8 => {
-                self.shaders.qtang_nm.bind();
-                self.shaders.qtang_nm.set_uniforms(normal_map_slot);
-                &mut self.shaders.qtang_nm
-            }
-            9 => {
-                self.shaders.bitang_nm.bind();
-                self.shaders.bitang_nm.set_uniforms(normal_map_slot);
-                &mut self.shaders.bitang_nm
-            }
-            10 => {
-                self.shaders.cayley_log_motor_nm.bind();
-                self.shaders
-                    .cayley_log_motor_nm
-                    .set_uniforms(normal_map_slot);
-                &mut self.shaders.cayley_log_motor_nm
-            }
-            11 => {
-                self.shaders.cayley_log_rotor_nm.bind();
-                self.shaders
-                    .cayley_log_rotor_nm
-                    .set_uniforms(normal_map_slot);
-                &mut self.shaders.cayley_log_rotor_nm
-            }
-            _ => unreachable!(),
-        }
+        let shader = &mut self.shaders.techniques[self.state.shader];
+        shader.bind();
+        shader.set_uniforms(|texture| match texture {
+            techniques::Texture::Albedo => ALBEDO_SLOT as i32,
+            techniques::Texture::NormalMap => NORMAL_MAP_SLOT as i32,
+            techniques::Texture::Roughness => ROUGHNESS_SLOT as i32,
+        });
+        shader
     }
 
-    /// Creates a model out of a mesh
-    fn make_model(mesh: &Mesh) -> Model {
+    /// Creates a model out of a mesh, with the material of the scene file it
+    /// names, the material of its file or the default material, in that
+    /// order. Textures of its file are uploaded unless they are in
+    /// `scene.uploads` already.
+    fn make_model(mesh: &Mesh, scene: &mut Scene) -> Model {
+        let default = scene.materials.get("default").cloned().unwrap_or_default();
+        let material = match (&mesh.material, &mesh.file_material) {
+            (Some(name), _) => scene.materials[name].clone(),
+            (None, Some(file)) => {
+                let uploads = &mut scene.uploads;
+                let mut upload = |image: &Arc<gltf_loader::Image>| {
+                    let texture = uploads
+                        .entry(image.key.clone())
+                        .or_insert_with(|| Rc::new(image.upload()));
+                    material::TextureHandle::Gltf(texture.clone())
+                };
+                material::Material {
+                    albedo: file.albedo,
+                    albedo_map: file.albedo_map.as_ref().map(&mut upload),
+                    normal_map: file.normal_map.as_ref().map(&mut upload),
+                    roughness_map: file.roughness_map.as_ref().map(&mut upload),
+                    ..default
+                }
+            }
+            (None, None) => default,
+        };
         let mut vao = pgl::vao::VertexArray::new_static();
         vao.bind();
         vao.buffer_indices(&mesh.faces);
@@ -309,96 +298,126 @@ impl App {
             n_vertices: mesh.vertices.len(),
             name: mesh.name.clone(),
             group: mesh.group.clone(),
-            active: false,
+            active: mesh.active,
+            transform: mesh.transform,
+            material,
         };
         model
     }
 
-    // Spawns threads that load and creates a mesh, returning the channel
-    // reciever that the finshed meshes are send to.
-    fn make_geometry() -> std::sync::mpsc::Receiver<Mesh> {
+    // Spawns a thread for every mesh source that loads and creates its
+    // meshes, returning the channel reciever that the finshed meshes are
+    // send to.
+    fn make_geometry(sources: &[scene_file::MeshSource]) -> std::sync::mpsc::Receiver<Mesh> {
         let (sender, receiver) = std::sync::mpsc::channel();
-        let sender_gun = sender.clone();
-        let sender_geom = sender.clone();
-        let sender_car = sender.clone();
-
-        // CAR
-        std::thread::spawn(move || {
-            if let Ok(gun) = pgeom::obj::load("models/bugatti/bugatti.obj") {
-                for mesh in gun.iter() {
-                    let (vertices, faces) = mesh.render_data(|v| {
-                        vertices::All::from(vertices::PosUVNormTang {
-                            position: v.position,
-                            uv: v.uv.unwrap_or_default(),
-                            normal: v.normal.unwrap_or_default(),
-                            tangent: [0., 0., 0.],
-                        })
-                    });
-                    let mesh = Mesh {
-                        vertices,
-                        faces,
-                        name: mesh.name.clone().unwrap_or("Dont know but from car".into()),
-                        group: "Bugatti".into(),
-                    };
-                    sender_car.send(mesh).unwrap();
+        for source in sources.iter().cloned() {
+            let sender = sender.clone();
+            std::thread::spawn(move || {
+                for mesh in Self::load_meshes(&source) {
+                    sender.send(mesh).unwrap();
                 }
-            };
-        });
+            });
+        }
+        receiver
+    }
 
-        // SHAPES
-        std::thread::spawn(move || {
-            [
-                (pgeom::sphere(200, 200), "Sphere"),
-                (pgeom::cylinder(10, 1), "Cylinder"),
-                (pgeom::rect(), "Rect"),
-                (pgeom::grid(100, 200), "Grid"),
-                (pgeom::monkey_saddle(200, 200), "Monkey Saddle"),
-            ]
-            .iter()
-            .for_each(|((vertices, faces), name)| {
-                let vertices = vertices
-                    .iter()
-                    .map(|v| vertices::PosUVNormTang {
-                        position: v.position,
-                        normal: v.normal,
-                        uv: v.uv,
-                        tangent: v.tangent,
-                    })
-                    .map(|v| vertices::All::from(v))
+    /// The meshes of a file or shape in the scene file. A file that can not
+    /// be loaded is reported and gives no meshes.
+    fn load_meshes(source: &scene_file::MeshSource) -> Vec<Mesh> {
+        let group = source.group();
+        let mut meshes = match (&source.path, &source.shape) {
+            (Some(path), _) if path.ends_with(".gltf") || path.ends_with(".glb") => {
+                gltf_loader::load(path, &group).unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    Vec::new()
+                })
+            }
+            (Some(path), _) => Self::load_obj(path, &group),
+            (None, Some(shape)) => vec![Self::make_shape(shape, &group)],
+            (None, None) => unreachable!(),
+        };
+        for mesh in meshes.iter_mut() {
+            mesh.active = source.active;
+            mesh.material = source.material.clone();
+        }
+        meshes
+    }
+
+    fn load_obj(path: &str, group: &str) -> Vec<Mesh> {
+        let obj = match pgeom::obj::load(path) {
+            Ok(obj) => obj,
+            Err(_) => {
+                eprintln!("Could not load {}", path);
+                return Vec::new();
+            }
+        };
+        obj.iter()
+            .map(|mesh| {
+                let (mut vertices, mut faces) = mesh.render_data(|v| vertices::PosUVNormTang {
+                    position: v.position,
+                    uv: v.uv.unwrap_or_default(),
+                    normal: v.normal.unwrap_or_default(),
+                    tangent: [0., 0., 0.],
+                    handedness: 1.,
+                });
+                tangents::generate(&mut vertices, &mut faces);
+                let mut vertices = vertices
+                    .into_iter()
+                    .map(vertices::All::from)
                     .collect::<Vec<_>>();
-                let m = Mesh {
-                    faces: faces.to_vec(),
+                let seams = antipodal::align(&mut vertices, &faces);
+                antipodal::split_seams(&mut vertices, &mut faces, &seams);
+                Mesh {
                     vertices,
-                    name: (*name).into(),
-                    group: (*name).into(),
-                };
-                sender_geom.send(m).unwrap();
-            });
-        });
-
-        // GUN
-        std::thread::spawn(move || {
-            if let Ok(gun) = pgeom::obj::load("models/gun/Handgun_obj.obj") {
-                for mesh in gun.iter() {
-                    let (vertices, faces) = mesh.render_data(|v| {
-                        vertices::All::from(vertices::PosUVNormTang {
-                            position: v.position,
-                            uv: v.uv.unwrap_or_default(),
-                            normal: v.normal.unwrap(),
-                            tangent: [0., 0., 0.],
-                        })
-                    });
-                    let m = Mesh {
-                        vertices,
-                        faces,
-                        name: mesh.name.clone().unwrap_or("Unknown Gun Part".into()),
-                        group: "Gun".into(),
-                    };
-                    sender_gun.send(m).unwrap();
+                    faces,
+                    name: mesh
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| format!("Unnamed {} Part", group)),
+                    group: group.into(),
+                    active: false,
+                    transform: glm::Mat4::identity(),
+                    material: None,
+                    file_material: None,
                 }
-            }
-        });
-        receiver
+            })
+            .collect()
+    }
+
+    /// One of `scene_file::SHAPES`.
+    fn make_shape(shape: &str, group: &str) -> Mesh {
+        let (vertices, faces) = match shape {
+            "sphere" => pgeom::sphere(200, 200),
+            "cylinder" => pgeom::cylinder(10, 1),
+            "rect" => pgeom::rect(),
+            "grid" => pgeom::grid(100, 200),
+            "monkey saddle" => pgeom::monkey_saddle(200, 200),
+            _ => unreachable!(),
+        };
+        let mut vertices = vertices
+            .iter()
+            .map(|v| vertices::PosUVNormTang {
+                position: v.position,
+                normal: v.normal,
+                uv: v.uv,
+                tangent: v.tangent,
+                handedness: 1.,
+            })
+            .map(vertices::All::from)
+            .collect::<Vec<_>>();
+        let mut faces = faces.to_vec();
+        let seams = antipodal::align(&mut vertices, &faces);
+        antipodal::split_seams(&mut vertices, &mut faces, &seams);
+        Mesh {
+            faces,
+            vertices,
+            name: group.into(),
+            group: group.into(),
+            active: false,
+            transform: glm::Mat4::identity(),
+            material: None,
+            file_material: None,
+        }
     }
 }
 
@@ -407,10 +426,14 @@ impl App {
 pub struct State {
     pub flying_cam: bool,
     pub bgcolor: [f32; 3],
-    pub n_draws: u32,
-    pub texture: usize,
-    pub normal_map: usize,
+    /// Copies of every model, drawn instanced.
+    pub n_instances: u32,
+    /// Index in `Scene::models` of the model the material editor edits.
+    pub selected_model: usize,
+    /// Index in `techniques::in_app()`.
     pub shader: usize,
+    /// Index in `shaders::DecodePlacement::ALL`.
+    pub decode_placement: usize,
     pub wireframe: bool,
     pub model_rotation_x: f32,
 }
@@ -426,31 +449,123 @@ struct Renderers {
 }
 
 struct Shaders {
-    pub flat: shaders::Flat,
-    pub textured: shaders::Textured,
-    pub matrix_nm: shaders::NormalMapping,
-    pub nm_tex: shaders::NormalAlbedoMapping,
-    pub rotor_nm: shaders::NormalMapping,
-    pub motor_nm: shaders::NormalMapping,
-    pub outer_log_motor_nm: shaders::NormalMapping,
-    pub outer_log_rotor_nm: shaders::NormalMapping,
-    pub cayley_log_motor_nm: shaders::NormalMapping,
-    pub cayley_log_rotor_nm: shaders::NormalMapping,
-    pub qtang_nm: shaders::NormalMapping,
-    pub bitang_nm: shaders::NormalMapping,
+    /// One for every technique in `techniques::in_app()`.
+    pub techniques: Vec<shaders::TechniqueShader>,
+    pub decode_placement: usize,
+    pub watcher: hot_reload::ShaderWatcher,
+    /// Name of the technique and compile log of every failed reload.
+    pub errors: Vec<(String, String)>,
+}
+
+impl Shaders {
+    /// Compiles the shader of every technique, with the decode placement
+    /// at index `decode_placement`.
+    fn new(decode_placement: usize) -> Self {
+        let placement = shaders::DecodePlacement::ALL[decode_placement];
+        let mut watcher = hot_reload::ShaderWatcher::new();
+        for technique in techniques::in_app() {
+            watcher.watch(technique.shader);
+        }
+        Self {
+            techniques: techniques::in_app()
+                .map(|t| shaders::TechniqueShader::new(t, placement))
+                .collect(),
+            decode_placement,
+            watcher,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Recompiles the shaders of which a file changed on disk. A shader
+    /// that fails to compile keeps its old program and its log is kept in
+    /// `errors` until it compiles again.
+    fn reload_changed(&mut self) {
+        let changed = self.watcher.poll();
+        if changed.is_empty() {
+            return;
+        }
+        let placement = shaders::DecodePlacement::ALL[self.decode_placement];
+        for shader in self.techniques.iter() {
+            let path = std::path::Path::new(shader.technique.shader);
+            if !changed.iter().any(|c| c == path) {
+                continue;
+            }
+            if Self::reload(shader, placement, &mut self.errors) {
+                println!("Reloaded {}", shader.technique.name);
+            }
+        }
+    }
+
+    /// Recompiles every shader with the decode placement at index
+    /// `decode_placement`, the ones that fail keep their old program like
+    /// in `reload_changed`.
+    fn set_decode_placement(&mut self, decode_placement: usize) {
+        self.decode_placement = decode_placement;
+        let placement = shaders::DecodePlacement::ALL[decode_placement];
+        for shader in self.techniques.iter() {
+            Self::reload(shader, placement, &mut self.errors);
+        }
+    }
+
+    /// Replaces the error of the shader by the one of this reload, if any.
+    /// Returns whether it compiled.
+    fn reload(
+        shader: &shaders::TechniqueShader,
+        placement: shaders::DecodePlacement,
+        errors: &mut Vec<(String, String)>,
+    ) -> bool {
+        let name = shader.technique.name;
+        errors.retain(|(n, _)| n != name);
+        match shader.reload(placement) {
+            Ok(()) => true,
+            Err(log) => {
+                errors.push((name.to_string(), log));
+                false
+            }
+        }
+    }
 }
 
-struct Textures {
-    pub world: pgl::texture::Texture,
-    pub brick_normals: pgl::texture::Texture,
-    pub wall_normals: pgl::texture::Texture,
-    pub wall_albedo: pgl::texture::Texture,
+/// A texture from the scene file, materials can be given it in the GUI.
+pub struct SceneTexture {
+    pub name: String,
+    pub kind: techniques::Texture,
+    pub texture: material::TextureHandle,
+}
+
+/// Bound in place of the maps a material leaves out: a white albedo, a
+/// flat normal map and no roughness.
+pub struct NeutralMaps {
+    pub albedo: material::TextureHandle,
+    pub normal_map: material::TextureHandle,
+    pub roughness: material::TextureHandle,
+}
+
+impl NeutralMaps {
+    fn new() -> Self {
+        let solid = |key, rgba| {
+            let image = gltf_loader::Image::solid(key, rgba);
+            material::TextureHandle::Gltf(Rc::new(image.upload()))
+        };
+        Self {
+            albedo: solid("neutral albedo", [255, 255, 255, 255]),
+            normal_map: solid("neutral normal map", [128, 128, 255, 255]),
+            roughness: solid("neutral roughness", [0, 0, 0, 255]),
+        }
+    }
 }
 
 pub struct Scene {
     pub model_reciever: std::sync::mpsc::Receiver<Mesh>,
     pub models: Vec<Model>,
-    pub material: material::Material,
+    /// The textures of glTF files, by `gltf_loader::Image::key`.
+    pub uploads: HashMap<String, Rc<gltf_loader::Texture>>,
+    pub textures: Vec<SceneTexture>,
+    pub neutral_maps: NeutralMaps,
+    /// The copies of every model, in its model space.
+    pub instances: instancing::InstanceBuffer,
+    /// The materials of the scene file by name.
+    pub materials: HashMap<String, material::Material>,
     pub light: lights::CameraFollowingLight,
     pub transform: glm::Mat4,
 }
@@ -463,6 +578,9 @@ pub struct Model {
     pub name: String,
     pub group: String,
     pub active: bool,
+    /// Applied before the transform of the scene.
+    pub transform: glm::Mat4,
+    pub material: material::Material,
 }
 
 /// The actual data desribing a model, can be discarded when
@@ -472,4 +590,14 @@ pub struct Mesh {
     pub vertices: Vec<vertices::All>,
     pub name: String,
     pub group: String,
+    /// Drawn from the start.
+    pub active: bool,
+    /// The rigid part of the transform of the node the mesh was loaded
+    /// from, the rest is baked into the vertices.
+    pub transform: glm::Mat4,
+    /// The name of a material in the scene file.
+    pub material: Option<String>,
+    /// The material of the file the mesh was loaded from, used when
+    /// `material` is left out.
+    pub file_material: Option<gltf_loader::MeshMaterial>,
 }
@@ -1,7 +1,15 @@
 use pthesis::*;
 
 fn main() {
-    let mut app = app::App::new();
+    let matches = clap::App::new("Normal Mapping Inspector")
+        .arg(
+            clap::Arg::with_name("scene")
+                .default_value("scene.toml")
+                .help("Scene file with the meshes, textures, material, light and camera"),
+        )
+        .get_matches();
+
+    let mut app = app::App::new(matches.value_of("scene").unwrap());
     while !app.should_stop() {
         pgl::utils::gl::check_error();
         pgl::utils::gl::flush_error();
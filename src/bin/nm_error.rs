@@ -0,0 +1,250 @@
+//! Measures the accuracy of the tangent frame encodings. Every encoding is
+//! decoded on the CPU (see `reference`) at random points of every triangle
+//! of a mesh, for random normal map values, and compared to the normal that
+//! the interpolated and renormalized TBN frame gives. Prints a table of the
+//! angular errors and appends them to a csv file.
+
+use prettytable::{Cell, Row, Table};
+use pthesis::reference::{self, Encoding};
+use pthesis::*;
+use rand::{Rng, SeedableRng};
+
+fn main() {
+    let matches = clap::App::new("Normal Mapping Error")
+        .arg(
+            clap::Arg::with_name("mesh")
+                .short("m")
+                .long("mesh")
+                .default_value("sphere")
+                .help("sphere, cylinder, grid, saddle or a path to an obj file")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("n_samples")
+                .short("n")
+                .long("n_samples")
+                .default_value("16")
+                .help("Number of random points per triangle")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("seed")
+                .short("s")
+                .long("seed")
+                .default_value("0")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .default_value("errors.csv")
+                .takes_value(true),
+        )
+        .get_matches();
+
+    let mesh_name = matches.value_of("mesh").unwrap();
+    let n_samples: usize = matches.value_of("n_samples").unwrap().parse().unwrap();
+    let seed: u64 = matches.value_of("seed").unwrap().parse().unwrap();
+    let output = matches.value_of("output").unwrap();
+
+    let (vertices, faces) = load_mesh(mesh_name);
+    println!(
+        "Sampling {} triangles {} times per encoding",
+        faces.len(),
+        n_samples
+    );
+
+    let mut table = Table::new();
+    table.add_row(Row::new(vec![
+        Cell::new("Encoding"),
+        Cell::new("Mean Error (deg)"),
+        Cell::new("Max Error (deg)"),
+        Cell::new("P50 (deg)"),
+        Cell::new("P95 (deg)"),
+        Cell::new("P99 (deg)"),
+    ]));
+    let exists = std::path::Path::new(output).exists();
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .append(true)
+        .create(true)
+        .open(output)
+        .unwrap();
+    let mut csv = csv::WriterBuilder::new()
+        .has_headers(!exists)
+        .from_writer(file);
+
+    for encoding in Encoding::ALL.iter() {
+        // Same seed for every encoding, so all are sampled at the same points.
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        let mut errors = Vec::with_capacity(faces.len() * n_samples);
+        for face in faces.iter() {
+            let v = [
+                &vertices[face[0] as usize],
+                &vertices[face[1] as usize],
+                &vertices[face[2] as usize],
+            ];
+            for _ in 0..n_samples {
+                let barycentric = random_barycentric(&mut rng);
+                let normal = random_tangent_space_normal(&mut rng);
+                let expected = tbn_normal(v, barycentric, normal);
+                let decoded = reference::fragment_normal(*encoding, v, barycentric, normal);
+                errors.push(angle_degrees(expected, decoded));
+            }
+        }
+
+        let record = summarize(mesh_name, encoding.name(), errors);
+        table.add_row(Row::new(vec![
+            Cell::new(&record.encoding),
+            Cell::new(&record.mean_error.to_string()),
+            Cell::new(&record.max_error.to_string()),
+            Cell::new(&record.p50_error.to_string()),
+            Cell::new(&record.p95_error.to_string()),
+            Cell::new(&record.p99_error.to_string()),
+        ]));
+        csv.serialize(record).unwrap();
+    }
+    csv.flush().unwrap();
+    table.printstd();
+}
+
+#[derive(serde::Serialize)]
+pub struct Record {
+    mesh: String,
+    encoding: String,
+    n_samples: usize,
+    n_invalid: usize,
+    mean_error: f32,
+    max_error: f32,
+    p50_error: f32,
+    p95_error: f32,
+    p99_error: f32,
+}
+
+/// Builds the record, NaN errors (a decoder that broke down) are counted
+/// separately instead of poisoning the statistics.
+fn summarize(mesh: &str, encoding: &str, errors: Vec<f32>) -> Record {
+    let n_samples = errors.len();
+    let mut errors = errors
+        .into_iter()
+        .filter(|e| e.is_finite())
+        .collect::<Vec<_>>();
+    let n_invalid = n_samples - errors.len();
+    errors.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let percentile = |p: f32| stats::percentile(&errors, p);
+    Record {
+        mesh: mesh.to_string(),
+        encoding: encoding.to_string(),
+        n_samples,
+        n_invalid,
+        mean_error: errors.iter().sum::<f32>() / errors.len() as f32,
+        max_error: errors.last().copied().unwrap_or(f32::NAN),
+        p50_error: percentile(0.5),
+        p95_error: percentile(0.95),
+        p99_error: percentile(0.99),
+    }
+}
+
+/// The baseline: the interpolated tangent, bitangent and normal, each
+/// renormalized before they are used as a frame.
+fn tbn_normal(v: [&vertices::All; 3], b: [f32; 3], n: [f32; 3]) -> [f32; 3] {
+    let bitangent = |v: &vertices::All| {
+        let b = reference::cross(v.normal, v.tangent);
+        [
+            b[0] * v.handedness,
+            b[1] * v.handedness,
+            b[2] * v.handedness,
+        ]
+    };
+    let t = reference::normalize(reference::interpolate(
+        [v[0].tangent, v[1].tangent, v[2].tangent],
+        b,
+    ));
+    let bt = reference::normalize(reference::interpolate(
+        [bitangent(v[0]), bitangent(v[1]), bitangent(v[2])],
+        b,
+    ));
+    let nm = reference::normalize(reference::interpolate(
+        [v[0].normal, v[1].normal, v[2].normal],
+        b,
+    ));
+    reference::normalize([
+        t[0] * n[0] + bt[0] * n[1] + nm[0] * n[2],
+        t[1] * n[0] + bt[1] * n[1] + nm[1] * n[2],
+        t[2] * n[0] + bt[2] * n[1] + nm[2] * n[2],
+    ])
+}
+
+fn angle_degrees(a: [f32; 3], b: [f32; 3]) -> f32 {
+    let d = a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+    d.clamp(-1., 1.).acos().to_degrees()
+}
+
+/// Uniformly distributed point on a triangle.
+fn random_barycentric(rng: &mut impl Rng) -> [f32; 3] {
+    let r1: f32 = rng.gen::<f32>().sqrt();
+    let r2: f32 = rng.gen();
+    [1. - r1, r1 * (1. - r2), r1 * r2]
+}
+
+/// Uniformly distributed direction on the upper hemisphere, like the
+/// values a normal map holds.
+fn random_tangent_space_normal(rng: &mut impl Rng) -> [f32; 3] {
+    let z: f32 = rng.gen();
+    let phi = rng.gen::<f32>() * 2. * std::f32::consts::PI;
+    let r = (1. - z * z).sqrt();
+    [r * phi.cos(), r * phi.sin(), z]
+}
+
+/// Loads one of the procedural shapes or an obj file, prepared the same
+/// way as in the app.
+fn load_mesh(name: &str) -> (Vec<vertices::All>, Vec<[u32; 3]>) {
+    let shape = match name {
+        "sphere" => Some(pgeom::sphere(200, 200)),
+        "cylinder" => Some(pgeom::cylinder(10, 1)),
+        "grid" => Some(pgeom::grid(100, 200)),
+        "saddle" => Some(pgeom::monkey_saddle(200, 200)),
+        _ => None,
+    };
+    let (mut vertices, mut faces) = if let Some((vertices, faces)) = shape {
+        let vertices = vertices
+            .iter()
+            .map(|v| {
+                vertices::All::from(vertices::PosUVNormTang {
+                    position: v.position,
+                    normal: v.normal,
+                    uv: v.uv,
+                    tangent: v.tangent,
+                    handedness: 1.,
+                })
+            })
+            .collect::<Vec<_>>();
+        (vertices, faces.to_vec())
+    } else {
+        let meshes = pgeom::obj::load(name).expect("Could not load obj file");
+        let mut vertices = Vec::new();
+        let mut faces = Vec::new();
+        for mesh in meshes.iter() {
+            let (mut v, mut f) = mesh.render_data(|v| vertices::PosUVNormTang {
+                position: v.position,
+                uv: v.uv.unwrap_or_default(),
+                normal: v.normal.unwrap_or_default(),
+                tangent: [0., 0., 0.],
+                handedness: 1.,
+            });
+            tangents::generate(&mut v, &mut f);
+            let offset = vertices.len() as u32;
+            faces.extend(
+                f.iter()
+                    .map(|f| [f[0] + offset, f[1] + offset, f[2] + offset]),
+            );
+            vertices.extend(v.into_iter().map(vertices::All::from));
+        }
+        (vertices, faces)
+    };
+    let seams = antipodal::align(&mut vertices, &faces);
+    antipodal::split_seams(&mut vertices, &mut faces, &seams);
+    (vertices, faces)
+}
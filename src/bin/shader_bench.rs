@@ -1,6 +1,19 @@
 //! Can be used for testing the performance of
-//! a shader provided in the command line path argument.
+//! a shader provided in the command line path argument,
+//! of registered techniques (`--techniques rotor,motor` or `all`)
+//! or of every encoding in a suite file (see `bench.toml`).
 //! Prints out a table of results.
+//!
+//! `bench report` analyses the results instead, see `report`, and
+//! `bench compare` checks them for regressions against a baseline file.
+//!
+//! With `--headless` (needs the `headless` feature) nothing is shown, the
+//! frames are rendered into an offscreen framebuffer instead of a window.
+//!
+//! `--stages vertex,fragment` also times the shader stages apart: the
+//! vertex stage by drawing into a 1 by 1 viewport, the fragment stage by
+//! drawing a full-screen quad (see `suite::Stage`). `--decode` compiles
+//! every encoding with each of the given decode placements.
 
 use pgl::{
     query::{Query, Target},
@@ -10,15 +23,34 @@ use pgl::{
 };
 use prettytable::{Cell, Row, Table};
 use pthesis::*;
+use shaders::DecodePlacement;
+use suite::Stage;
 
 fn main() {
     let matches = clap::App::new("Shader Bencher")
+        .setting(clap::AppSettings::SubcommandsNegateReqs)
+        .arg(
+            clap::Arg::with_name("suite")
+                .short("s")
+                .long("suite")
+                .help("Suite file listing the benchmarks, replaces the other options")
+                .takes_value(true),
+        )
         .arg(
             clap::Arg::with_name("path")
                 .short("p")
                 .long("paths")
-                .required(true)
+                .required_unless_one(&["suite", "techniques"])
+                .multiple(true)
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("techniques")
+                .long("techniques")
+                .help("Registered techniques to benchmark (see techniques), or all encodings")
                 .multiple(true)
+                .use_delimiter(true)
+                .conflicts_with("path")
                 .takes_value(true),
         )
         .arg(
@@ -49,57 +81,200 @@ fn main() {
                 .default_value("all")
                 .takes_value(true),
         )
+        .arg(
+            clap::Arg::with_name("mesh")
+                .long("mesh")
+                .default_value("grid")
+                .help("grid, sphere, saddle or a path to an obj file")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("camera")
+                .long("camera")
+                .default_value("ortho")
+                .possible_values(&["ortho", "perspective"])
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("stages")
+                .long("stages")
+                .default_value("full")
+                .possible_values(&["full", "vertex", "fragment"])
+                .multiple(true)
+                .use_delimiter(true)
+                .help("Time the whole draw, or isolate the vertex or fragment shader")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("decode")
+                .long("decode")
+                .default_value("default")
+                .possible_values(&["default", "per-vertex", "per-fragment", "hybrid"])
+                .multiple(true)
+                .use_delimiter(true)
+                .help("Where the shaders decode the tangent frame, see shaders::DecodePlacement")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("warmup")
+                .long("warmup")
+                .default_value("5")
+                .help("Number of frames that are drawn before measuring")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("outlier_iqr")
+                .long("outlier_iqr")
+                .default_value("1.5")
+                .help("Rejects frame times this many interquartile ranges outside the quartiles, 0 keeps all")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("headless")
+                .long("headless")
+                .help("Render offscreen, without opening a window"),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("report")
+                .about("Compares the encodings in a results file against a baseline")
+                .arg(
+                    clap::Arg::with_name("input")
+                        .short("i")
+                        .long("input")
+                        .default_value("results.csv")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .default_value("report")
+                        .help("Writes <output>.md, <output>.html and <output>.svg")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("run")
+                        .short("r")
+                        .long("run")
+                        .help("Run ID to report on, all for every run, the last run by default")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("baseline")
+                        .short("b")
+                        .long("baseline")
+                        .default_value("matrix")
+                        .help("Encoding the speed-ups are relative to")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("statistic")
+                        .long("statistic")
+                        .default_value("mean")
+                        .possible_values(&["mean", "median"])
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("compare")
+                .about("Flags significant changes in draw time against a baseline results file, exits with 1 on regressions")
+                .arg(
+                    clap::Arg::with_name("baseline")
+                        .short("b")
+                        .long("baseline")
+                        .required(true)
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("input")
+                        .short("i")
+                        .long("input")
+                        .default_value("results.csv")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("run")
+                        .short("r")
+                        .long("run")
+                        .help("Run ID in the input, the last run by default")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("baseline_run")
+                        .long("baseline_run")
+                        .help("Run ID in the baseline, the last run by default")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("alpha")
+                        .long("alpha")
+                        .default_value("0.05")
+                        .help("Significance level of Welch's t-test")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::with_name("threshold")
+                        .short("t")
+                        .long("threshold")
+                        .default_value("5")
+                        .help("Smallest change in mean draw time (%) that counts")
+                        .takes_value(true),
+                ),
+        )
         .get_matches();
 
-    let n_iter = matches.value_of("n_iter").unwrap();
-    let filepaths = matches.values_of("path").unwrap();
-    let width = matches.value_of("width").unwrap().parse().unwrap();
-    let height = matches.value_of("height").unwrap().parse().unwrap();
-    let vertex = matches.value_of("vertex").unwrap();
-
-    let window = GlfwWindow::new(width, height, "Benchmark");
-    let fac = window.hidpi_factor() as isize;
-    if fac != 1 {
-        window.set_window_size(width / fac, height / fac)
+    if let Some(matches) = matches.subcommand_matches("report") {
+        write_report(matches);
+        return;
+    }
+    if let Some(matches) = matches.subcommand_matches("compare") {
+        if !compare(matches) {
+            std::process::exit(1);
+        }
+        return;
     }
+
+    let suite = match matches.value_of("suite") {
+        Some(path) => suite::Suite::from_path(path),
+        None => suite_from_args(&matches),
+    };
+    let outlier_iqr = if suite.outlier_iqr > 0. {
+        Some(suite.outlier_iqr)
+    } else {
+        None
+    };
+    let run_id = suite::new_run_id();
+
+    let [width, height] = suite.resolutions[0];
+    let mut target = if matches.is_present("headless") {
+        RenderTarget::headless(width, height)
+    } else {
+        RenderTarget::window(width, height)
+    };
     pgl::utils::gl::set_default_options();
 
     let nm = pgl::texture::Texture::from_path("imgs/wall_normals.jpeg", Default::default());
     nm.bind_to(1).unwrap();
 
-    let camera = BenchCamera::default();
-
-    let (w, h) = window.framebuffer_size();
-    let n_fragments = w * h;
-    let grid_size1 = (n_fragments as f32 * 0.8).sqrt() as usize;
-    let grid_size2 = (n_fragments as f32 * 0.4).sqrt() as usize;
-    let sizes = [grid_size1, grid_size2];
-    let benches = match vertex {
-        "all" => create_bench_datas::<vertices::All>(&sizes),
-        "matrix" => create_bench_datas::<vertices::Matrix>(&sizes),
-        "normtang" => create_bench_datas::<vertices::PosUVNormTang>(&sizes),
-        "rotor" => create_bench_datas::<vertices::Rotor>(&sizes),
-        "outerrotor" => create_bench_datas::<vertices::OuterRotor>(&sizes),
-        "qrotor" => create_bench_datas::<vertices::QRotor>(&sizes),
-        "motor" => create_bench_datas::<vertices::Motor>(&sizes),
-        "outermotor" => create_bench_datas::<vertices::OuterMotor>(&sizes),
-        "cayleymotor" => create_bench_datas::<vertices::CayleyMotor>(&sizes),
-        "cayleyrotor" => create_bench_datas::<vertices::CayleyRotor>(&sizes),
-        _ => unimplemented!(),
-    };
-
     let mut unis = shaders::AppUniforms::new();
-    unis.update(&camera, &window, [0., 3., 0.], [0.5, 0.5, 0.5]);
-    unis.set_ubo();
 
     let mut table = Table::new();
     table.add_row(Row::new(vec![
+        Cell::new("Encoding"),
         Cell::new("File"),
+        Cell::new("Workload"),
+        Cell::new("Stage"),
+        Cell::new("Decode"),
         Cell::new("Average Drawtime (ms)"),
+        Cell::new("Median (ms)"),
+        Cell::new("95% CI (ms)"),
+        Cell::new("P99 (ms)"),
         Cell::new("Std"),
+        Cell::new("Outliers"),
         Cell::new("N Indices"),
         Cell::new("N Vertices"),
         Cell::new("N Fragments"),
+        Cell::new("Vertex Size (bytes)"),
     ]));
     let exists = std::path::Path::new("results.csv").exists();
     let file = std::fs::OpenOptions::new()
@@ -109,165 +284,718 @@ fn main() {
         .open("results.csv")
         .unwrap();
     let mut csv = csv::WriterBuilder::new()
-        .has_headers(if !exists { true } else { false })
+        .has_headers(!exists)
         .from_writer(file);
 
-    println!("{:?}", "Starting Benches");
-
-    for filepath in filepaths {
-        for data in benches.iter() {
-            bench(
-                &data,
-                &vertex,
-                &mut table,
-                &mut csv,
-                filepath,
-                n_iter.parse().unwrap(),
-                &window,
-            );
+    println!("Starting run {}", run_id);
+
+    // Every vertex layout is built once per resolution, for all the
+    // encodings that read it.
+    let mut layouts = Vec::new();
+    for encoding in suite.encodings.iter() {
+        if !layouts.contains(&encoding.vertex.as_str()) {
+            layouts.push(encoding.vertex.as_str());
+        }
+    }
+    let densities = suite.densities();
+    for &[width, height] in suite.resolutions.iter() {
+        target.resize(width, height);
+
+        let (w, h) = target.framebuffer_size();
+        let n_fragments = w * h;
+        for workload in suite.workloads.iter() {
+            // The vertices of all layers together make up the density.
+            let sizes = densities
+                .iter()
+                .map(|density| {
+                    (n_fragments as f32 * density / workload.layers as f32).sqrt() as usize
+                })
+                .collect::<Vec<_>>();
+            for vertex in layouts.iter() {
+                let benches = bench_datas(vertex, workload, &sizes, target.aspect());
+                for encoding in suite.encodings.iter().filter(|e| e.vertex == *vertex) {
+                    for data in benches.iter() {
+                        unis.update(&data.camera, target.aspect(), [0., 3., 0.], [0.5, 0.5, 0.5]);
+                        unis.set_ubo();
+                        let stages = suite.stages.iter().filter(|s| **s != Stage::Fragment);
+                        for &stage in stages {
+                            for &placement in suite.decode_placements.iter() {
+                                bench(
+                                    data,
+                                    encoding,
+                                    workload,
+                                    stage,
+                                    placement,
+                                    &run_id,
+                                    &mut table,
+                                    &mut csv,
+                                    suite.n_iter,
+                                    suite.warmup,
+                                    outlier_iqr,
+                                    &target,
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // The fragment stage does not depend on the mesh, so every
+        // encoding draws the same full-screen quad once per resolution.
+        if suite.stages.contains(&Stage::Fragment) {
+            let quad = suite::Workload {
+                name: "quad".to_string(),
+                ..Default::default()
+            };
+            for vertex in layouts.iter() {
+                let benches = bench_datas(vertex, &quad, &[2], target.aspect());
+                for encoding in suite.encodings.iter().filter(|e| e.vertex == *vertex) {
+                    for data in benches.iter() {
+                        unis.update(&data.camera, target.aspect(), [0., 3., 0.], [0.5, 0.5, 0.5]);
+                        unis.set_ubo();
+                        for &placement in suite.decode_placements.iter() {
+                            bench(
+                                data,
+                                encoding,
+                                &quad,
+                                Stage::Fragment,
+                                placement,
+                                &run_id,
+                                &mut table,
+                                &mut csv,
+                                suite.n_iter,
+                                suite.warmup,
+                                outlier_iqr,
+                                &target,
+                            );
+                        }
+                    }
+                }
+            }
         }
     }
     csv.flush().unwrap();
     table.printstd();
 }
 
+fn write_report(matches: &clap::ArgMatches) {
+    let rows = report::read_results(matches.value_of("input").unwrap());
+    let report = report::Report::new(
+        &rows,
+        matches.value_of("run"),
+        matches.value_of("baseline").unwrap(),
+        report::Statistic::from_name(matches.value_of("statistic").unwrap()),
+    );
+    let output = matches.value_of("output").unwrap();
+    let markdown = report.to_markdown();
+    std::fs::write(format!("{}.md", output), &markdown).unwrap();
+    std::fs::write(format!("{}.html", output), report.to_html()).unwrap();
+    std::fs::write(format!("{}.svg", output), report.to_svg()).unwrap();
+    println!("{}", markdown);
+}
+
+/// Prints the differences with the baseline, returns false when there
+/// are regressions.
+fn compare(matches: &clap::ArgMatches) -> bool {
+    let baseline = report::read_results(matches.value_of("baseline").unwrap());
+    let candidate = report::read_results(matches.value_of("input").unwrap());
+    let (baseline_run, baseline) = report::select_run(&baseline, matches.value_of("baseline_run"));
+    let (run, candidate) = report::select_run(&candidate, matches.value_of("run"));
+    let alpha: f32 = matches.value_of("alpha").unwrap().parse().unwrap();
+    let threshold: f32 = matches.value_of("threshold").unwrap().parse().unwrap();
+    let differences = report::compare(&baseline, &candidate, alpha, threshold / 100.);
+
+    println!("Run {} against baseline run {}", run, baseline_run);
+    let mut table = Table::new();
+    table.add_row(Row::new(vec![
+        Cell::new("Encoding"),
+        Cell::new("N Vertices"),
+        Cell::new("N Fragments"),
+        Cell::new("Baseline (ms)"),
+        Cell::new("Candidate (ms)"),
+        Cell::new("Change (%)"),
+        Cell::new("p"),
+        Cell::new("Verdict"),
+    ]));
+    for d in differences.iter() {
+        table.add_row(Row::new(vec![
+            Cell::new(&d.encoding),
+            Cell::new(&d.n_vertices.to_string()),
+            Cell::new(&d.n_fragments.map(|n| n.to_string()).unwrap_or_default()),
+            Cell::new(&d.baseline.to_string()),
+            Cell::new(&d.candidate.to_string()),
+            Cell::new(&format!("{:+.2}", d.change * 100.)),
+            Cell::new(&format!("{:.4}", d.p)),
+            Cell::new(d.verdict.name()),
+        ]));
+    }
+    table.printstd();
+
+    let n_regressions = differences
+        .iter()
+        .filter(|d| d.verdict == report::Verdict::Regression)
+        .count();
+    if n_regressions > 0 {
+        println!("{} regressions", n_regressions);
+    }
+    n_regressions == 0
+}
+
+/// A suite of the shaders given with `--paths`, all reading the layout
+/// given with `--vertex`.
+fn suite_from_args(matches: &clap::ArgMatches) -> suite::Suite {
+    let vertex = matches.value_of("vertex").unwrap();
+    let encodings =
+        match matches.values_of("techniques") {
+            Some(names) => names
+                .flat_map(|name| match name {
+                    "all" => techniques::TECHNIQUES
+                        .iter()
+                        .filter(|t| t.encoding)
+                        .collect::<Vec<_>>(),
+                    _ => vec![techniques::find(name)
+                        .unwrap_or_else(|| panic!("Unknown technique {}", name))],
+                })
+                .map(suite::Encoding::from_technique)
+                .collect(),
+            None => matches
+                .values_of("path")
+                .unwrap()
+                .map(|path| suite::Encoding {
+                    name: std::path::Path::new(path)
+                        .file_stem()
+                        .unwrap()
+                        .to_str()
+                        .unwrap()
+                        .to_string(),
+                    shader: path.to_string(),
+                    vertex: vertex.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+        };
+    suite::Suite {
+        n_iter: matches.value_of("n_iter").unwrap().parse().unwrap(),
+        warmup: matches.value_of("warmup").unwrap().parse().unwrap(),
+        outlier_iqr: matches.value_of("outlier_iqr").unwrap().parse().unwrap(),
+        resolutions: vec![[
+            matches.value_of("width").unwrap().parse().unwrap(),
+            matches.value_of("height").unwrap().parse().unwrap(),
+        ]],
+        densities: vec![0.8, 0.4],
+        density_sweep: None,
+        workloads: vec![suite::Workload {
+            name: matches.value_of("mesh").unwrap().to_string(),
+            mesh: matches.value_of("mesh").unwrap().to_string(),
+            camera: match matches.value_of("camera").unwrap() {
+                "perspective" => suite::Camera::Perspective,
+                _ => suite::Camera::Ortho,
+            },
+            ..Default::default()
+        }],
+        stages: matches
+            .values_of("stages")
+            .unwrap()
+            .map(Stage::from_name)
+            .collect(),
+        decode_placements: matches
+            .values_of("decode")
+            .unwrap()
+            .map(DecodePlacement::from_name)
+            .collect(),
+        encodings,
+    }
+}
+
+/// The vertex layouts by the name used in suites and with `--vertex`, see
+/// `for_each_layout!`.
+fn bench_datas(
+    vertex: &str,
+    workload: &suite::Workload,
+    sizes: &[usize],
+    aspect: f32,
+) -> Vec<BenchData> {
+    macro_rules! bench_datas {
+        ($( $kind:ident $name:literal $layout:ty, )*) => {
+            match vertex {
+                $( $name => bench_datas!(@ $kind $layout), )*
+                _ => panic!("Unknown vertex layout {}", vertex),
+            }
+        };
+        (@ f32 $layout:ty) => {
+            create_bench_datas::<$layout>(workload, sizes, aspect)
+        };
+        (@ packed $layout:ty) => {
+            create_packed_bench_datas::<$layout>(workload, sizes, aspect)
+        };
+    }
+    for_each_layout!(bench_datas)
+}
+
+/// Where the frames are rendered to.
+enum RenderTarget {
+    Window(GlfwWindow),
+    #[cfg(feature = "headless")]
+    Headless(headless::HeadlessContext),
+}
+
+impl RenderTarget {
+    fn window(width: usize, height: usize) -> Self {
+        let window = GlfwWindow::new(width as _, height as _, "Benchmark");
+        let fac = window.hidpi_factor() as usize;
+        if fac != 1 {
+            window.set_window_size((width / fac) as _, (height / fac) as _)
+        }
+        RenderTarget::Window(window)
+    }
+
+    #[cfg(feature = "headless")]
+    fn headless(width: usize, height: usize) -> Self {
+        RenderTarget::Headless(headless::HeadlessContext::new(width, height))
+    }
+
+    #[cfg(not(feature = "headless"))]
+    fn headless(_width: usize, _height: usize) -> Self {
+        panic!("--headless needs the headless feature, run with --features headless")
+    }
+
+    /// Changes the size of the framebuffer, a window can end up smaller
+    /// than asked for when it does not fit on the screen.
+    fn resize(&mut self, width: usize, height: usize) {
+        match self {
+            RenderTarget::Window(window) => {
+                let fac = window.hidpi_factor() as usize;
+                window.set_window_size((width / fac) as _, (height / fac) as _);
+                window.poll_events();
+                let (w, h) = window.framebuffer_size();
+                unsafe { gl::Viewport(0, 0, w as _, h as _) };
+            }
+            #[cfg(feature = "headless")]
+            RenderTarget::Headless(context) => context.resize(width, height),
+        }
+    }
+
+    fn framebuffer_size(&self) -> (usize, usize) {
+        match self {
+            RenderTarget::Window(window) => {
+                let (w, h) = window.framebuffer_size();
+                (w as usize, h as usize)
+            }
+            #[cfg(feature = "headless")]
+            RenderTarget::Headless(context) => context.framebuffer_size(),
+        }
+    }
+
+    fn aspect(&self) -> f32 {
+        match self {
+            RenderTarget::Window(window) => window.aspect(),
+            #[cfg(feature = "headless")]
+            RenderTarget::Headless(context) => context.aspect(),
+        }
+    }
+
+    /// Shows the frame, returns false when the benchmark should stop.
+    fn present(&self) -> bool {
+        match self {
+            RenderTarget::Window(window) => {
+                window.poll_events();
+                window.swap_buffers();
+                !window.should_close()
+            }
+            #[cfg(feature = "headless")]
+            RenderTarget::Headless(_) => true,
+        }
+    }
+}
+
 struct BenchData {
     vao: pgl::vao::VertexArray,
+    _packed: Option<packed::VertexBuffer>, // kept alive for the packed layouts
+    instance_buffer: Option<instancing::InstanceBuffer>, // when instanced
     n_indices: usize,
     n_vertices: usize,
+    vertex_size: usize,
+    define: &'static str, // selects the attribute block of the layout
+    instances: usize,
+    camera: BenchCamera,
 }
 
+#[allow(clippy::too_many_arguments)]
 fn bench(
     data: &BenchData,
-    vertex: &str,
+    encoding: &suite::Encoding,
+    workload: &suite::Workload,
+    stage: Stage,
+    placement: DecodePlacement,
+    run_id: &str,
     table: &mut Table,
     csv: &mut csv::Writer<std::fs::File>,
-    filename: impl AsRef<std::path::Path>,
     n_frames: usize,
-    window: &GlfwWindow,
+    warmup: usize,
+    outlier_iqr: Option<f32>,
+    target: &RenderTarget,
 ) {
-    let attr_define = match vertex {
-        "all" => "ALL",
-        "matrix" => "MATRIX_ATTRIBUTES",
-        "normtang" => "NORMTANG_ATTRIBUTES",
-        "motor" => "MOTOR_ATTRIBUTES",
-        "rotor" => "ROTOR_ATTRIBUTES",
-        "outermotor" => "OUTER_MOTOR_ATTRIBUTES",
-        "outerrotor" => "OUTER_ROTOR_ATTRIBUTES",
-        "qrotor" => "QROTOR_ATTRIBUTES",
-        "cayleyrotor" => "CAYLEY_ROTOR_ATTRIBUTES",
-        "cayleymotor" => "CAYLEY_MOTOR_ATTRIBUTES",
-        _ => unimplemented!(),
-    };
-    let ops = pgl::shader::ShaderOptions {
-        vs_defines: vec![attr_define.into()],
-        ..Default::default()
-    };
-    let mut shader = ShaderProgram::from_path(&filename, ops).unwrap();
+    let mut ops = placement.shader_options();
+    ops.vs_defines.extend(encoding.defines.iter().cloned());
+    ops.fs_defines.extend(encoding.defines.iter().cloned());
+    ops.vs_defines.push(data.define.into());
+    if data.instances > 1 {
+        ops.vs_defines.push(instancing::DEFINE.into());
+        ops.fs_defines.push(instancing::DEFINE.into());
+    }
+    let mut shader = shaders::load(&encoding.shader, ops).unwrap();
 
     let mut draw_times: Vec<i64> = Vec::new();
 
     shader.bind();
     shader.bind_uniform_block("App", 0);
+    if let Some(instances) = &data.instance_buffer {
+        instancing::bind_block(&shader);
+        instances.bind();
+    }
     shader.set_int("uNormalMap", 1);
+    set_bones(&mut shader, &encoding.vertex);
 
     data.vao.bind();
 
-    for _ in 0..n_frames {
+    let (w, h) = target.framebuffer_size();
+    if stage == Stage::Vertex {
+        // Every vertex is still shaded, but hardly any fragment.
+        unsafe { gl::Viewport(0, 0, 1, 1) };
+    }
+
+    for frame in 0..warmup + n_frames {
         pgl::utils::gl::check_error();
         pgl::utils::gl::flush_error();
 
         let timer = Query::new(Target::TimeElapsed);
         timer.begin();
-        pgl::utils::gl::draw(data.n_indices);
+        if data.instances > 1 {
+            instancing::draw(data.n_indices, data.instances);
+        } else {
+            pgl::utils::gl::draw(data.n_indices);
+        }
         timer.end();
-        draw_times.push(timer.result());
+        let time = timer.result();
+        if frame >= warmup {
+            draw_times.push(time);
+        }
 
-        window.poll_events();
-        window.swap_buffers();
-        if window.should_close() {
+        if !target.present() {
             break;
         }
         pgl::utils::gl::clear();
     }
+    unsafe { gl::Viewport(0, 0, w as _, h as _) };
 
-    let fname = filename.as_ref().file_name().unwrap().to_str().unwrap();
-    let skip = 5;
+    let fname = std::path::Path::new(&encoding.shader)
+        .file_name()
+        .unwrap()
+        .to_str()
+        .unwrap();
     let draw_times = draw_times
         .iter()
-        .skip(skip) // first few frames take longer
         .map(|t| *t as f32 / 1000_000.0)
         .collect::<Vec<_>>();
-    let ave_time = draw_times.iter().sum::<f32>() / draw_times.len() as f32;
-    let std =
-        (draw_times.iter().map(|t| t - ave_time).sum::<f32>() / draw_times.len() as f32).sqrt();
-    let (w, h) = window.framebuffer_size();
-    let n_frags = w * h;
+    let summary = stats::summarize(&draw_times, outlier_iqr);
+    // The vertex stage rasterizes into a single pixel, how many fragments
+    // that takes is not measured.
+    let n_pixels = w * h;
+    let n_frags = if stage == Stage::Vertex {
+        None
+    } else {
+        Some(n_pixels)
+    };
 
     table.add_row(Row::new(vec![
+        Cell::new(&encoding.name),
         Cell::new(fname),
-        Cell::new(&ave_time.to_string()),
-        Cell::new(&std.to_string()),
+        Cell::new(&workload.name),
+        Cell::new(stage.name()),
+        Cell::new(placement.name()),
+        Cell::new(&summary.mean.to_string()),
+        Cell::new(&summary.median.to_string()),
+        Cell::new(&format!("{} - {}", summary.ci_low, summary.ci_high)),
+        Cell::new(&summary.p99.to_string()),
+        Cell::new(&summary.std.to_string()),
+        Cell::new(&summary.n_outliers.to_string()),
         Cell::new(&data.n_indices.to_string()),
         Cell::new(&data.n_vertices.to_string()),
-        Cell::new(&n_frags.to_string()),
+        Cell::new(&n_frags.map(|n| n.to_string()).unwrap_or_default()),
+        Cell::new(&data.vertex_size.to_string()),
     ]));
 
     csv.serialize(Record {
+        run_id: run_id.to_string(),
+        encoding: encoding.name.clone(),
         filename: fname.to_string(),
-        average_drawtime: ave_time,
-        std,
+        workload: workload.name.clone(),
+        stage: stage.name().to_string(),
+        decode: placement.name().to_string(),
+        average_drawtime: summary.mean,
+        std: summary.std,
+        median_drawtime: summary.median,
+        trimmed_mean_drawtime: summary.trimmed_mean,
+        min_drawtime: summary.min,
+        max_drawtime: summary.max,
+        p95_drawtime: summary.p95,
+        p99_drawtime: summary.p99,
+        ci_low: summary.ci_low,
+        ci_high: summary.ci_high,
+        n_samples: summary.n,
+        n_outliers: summary.n_outliers,
+        warmup,
         n_indices: data.n_indices,
         n_vertices: data.n_vertices,
-        n_fragments: n_frags as usize,
+        n_instances: data.instances,
+        n_fragments: n_frags,
+        n_pixels,
+        vertex: encoding.vertex.clone(),
+        vertex_size: data.vertex_size,
     })
     .unwrap();
 }
 
+/// Bend of every joint of the chain that the skinned layouts are drawn
+/// with, in radians.
+const SKIN_BEND: f32 = 0.15;
+
+/// Poses the chain the skinned layouts are built for, see `skinning`. The
+/// other layouts have no bones.
+fn set_bones(shader: &mut ShaderProgram, vertex: &str) {
+    let skeleton = skinning::Skeleton::chain();
+    // Around the view direction, so the mesh curls in the image.
+    let pose = skeleton.bent(SKIN_BEND, &glm::Vec3::y());
+    match vertex {
+        "skinnedmotor" => shader.set_vec4fs("uBoneMotors", &skeleton.bone_motors(&pose)),
+        "skinnedmatrix" => shader.set_mat4fs("uBoneMatrices", &skeleton.bone_matrices(&pose)),
+        _ => {}
+    }
+}
+
 #[derive(serde::Serialize)]
 pub struct Record {
+    run_id: String,
+    encoding: String,
     filename: String,
+    workload: String,
+    stage: String,
+    decode: String,
     average_drawtime: f32,
     std: f32,
+    median_drawtime: f32,
+    trimmed_mean_drawtime: f32,
+    min_drawtime: f32,
+    max_drawtime: f32,
+    p95_drawtime: f32,
+    p99_drawtime: f32,
+    ci_low: f32,
+    ci_high: f32,
+    n_samples: usize,
+    n_outliers: usize,
+    warmup: usize,
     n_vertices: usize,
+    n_instances: usize,
     n_indices: usize,
-    n_fragments: usize,
+    /// Empty for the vertex stage.
+    n_fragments: Option<usize>,
+    /// Size of the framebuffer, the densities are relative to it.
+    n_pixels: usize,
+    vertex: String,
+    vertex_size: usize,
 }
 
+/// Looks down on the mesh along the negative y axis.
 struct BenchCamera {
     pos: glm::Vec3,
+    target: glm::Vec3,
+    projection: glm::Mat4,
 }
 
-impl Default for BenchCamera {
-    fn default() -> Self {
-        Self {
-            pos: [0., 4., 0.].into(),
+impl BenchCamera {
+    fn new(workload: &suite::Workload, bounds: &Bounds, aspect: f32) -> Self {
+        // The grid fills the whole screen, as it always has in these
+        // benchmarks, the other meshes are fully in view.
+        let (extent, depth) = if workload.mesh == "grid" {
+            (0.5, 0.)
+        } else {
+            (bounds.radius, bounds.radius)
+        };
+        let target = bounds.center;
+        match workload.camera {
+            suite::Camera::Ortho => {
+                let distance = 4. * bounds.radius.max(1.);
+                Self {
+                    pos: target + glm::Vec3::y() * distance,
+                    target,
+                    projection: glm::ortho(-extent, extent, -extent, extent, 0.1, 5. * distance),
+                }
+            }
+            suite::Camera::Perspective => {
+                let fovy = std::f32::consts::FRAC_PI_4;
+                let distance = extent / (fovy / 2.).tan() + depth;
+                let far = distance + 2. * bounds.radius + 1.;
+                Self {
+                    pos: target + glm::Vec3::y() * distance,
+                    target,
+                    projection: glm::perspective(aspect, fovy, 0.01 * distance, far),
+                }
+            }
         }
     }
 }
 
 impl cameras::Eye for BenchCamera {
     fn view(&self) -> glm::Mat4 {
-        glm::look_at::<f32>(&self.pos, &glm::Vec3::zeros(), &-glm::Vec3::z())
+        glm::look_at::<f32>(&self.pos, &self.target, &-glm::Vec3::z())
     }
     fn projection(&self) -> glm::Mat4 {
-        glm::ortho::<f32>(-0.5, 0.5, -0.5, 0.5, 0.1, 20.)
+        self.projection
     }
     fn position(&self) -> glm::Vec3 {
         self.pos
     }
 }
 
-fn create_bench_datas<V>(grid_sizes: &[usize]) -> Vec<BenchData>
+/// Bounding sphere of a mesh.
+struct Bounds {
+    center: glm::Vec3,
+    radius: f32,
+}
+
+impl Bounds {
+    fn of(vertices: &[vertices::PosUVNormTang]) -> Self {
+        let mut min = glm::Vec3::repeat(f32::INFINITY);
+        let mut max = glm::Vec3::repeat(f32::NEG_INFINITY);
+        for v in vertices.iter() {
+            let p: glm::Vec3 = v.position.into();
+            min = glm::min2(&min, &p);
+            max = glm::max2(&max, &p);
+        }
+        let center = (min + max) / 2.;
+        let radius = vertices
+            .iter()
+            .map(|v| glm::distance(&center, &v.position.into()))
+            .fold(0f32, f32::max);
+        Self { center, radius }
+    }
+}
+
+struct BenchMesh<V> {
+    vertices: Vec<V>,
+    faces: Vec<pgeom::types::Face>,
+    bounds: Bounds,
+}
+
+fn create_bench_datas<V>(workload: &suite::Workload, sizes: &[usize], aspect: f32) -> Vec<BenchData>
 where
-    V: 'static + pgl::vao::HasVertexAttributes + From<vertices::PosUVNormTang> + Send,
+    V: 'static
+        + pgl::vao::HasVertexAttributes
+        + layout::GlslLayout
+        + for<'a> From<&'a vertices::All>
+        + Send,
 {
+    meshes::<V>(workload, sizes)
+        .iter()
+        .map(|m| {
+            let mut vao = VertexArray::new_static();
+            vao.bind();
+            vao.buffer_indices(&m.faces);
+            vao.new_vertex_buffer_filled("all", &m.vertices);
+            BenchData {
+                vao,
+                _packed: None,
+                instance_buffer: instance_buffer(workload, &m.bounds),
+                n_indices: m.faces.len() * 3,
+                n_vertices: m.vertices.len(),
+                vertex_size: std::mem::size_of::<V>(),
+                define: V::DEFINE,
+                instances: workload.instances,
+                camera: BenchCamera::new(workload, &m.bounds, aspect),
+            }
+        })
+        .collect::<Vec<_>>()
+}
+
+fn create_packed_bench_datas<V>(
+    workload: &suite::Workload,
+    sizes: &[usize],
+    aspect: f32,
+) -> Vec<BenchData>
+where
+    V: 'static
+        + packed::HasPackedVertexAttributes
+        + layout::GlslLayout
+        + for<'a> From<&'a vertices::All>
+        + Send,
+{
+    meshes::<V>(workload, sizes)
+        .iter()
+        .map(|m| {
+            let vao = VertexArray::new_static();
+            vao.bind();
+            vao.buffer_indices(&m.faces);
+            let buffer = packed::VertexBuffer::new(&m.vertices);
+            BenchData {
+                vao,
+                _packed: Some(buffer),
+                instance_buffer: instance_buffer(workload, &m.bounds),
+                n_indices: m.faces.len() * 3,
+                n_vertices: m.vertices.len(),
+                vertex_size: std::mem::size_of::<V>(),
+                define: V::DEFINE,
+                instances: workload.instances,
+                camera: BenchCamera::new(workload, &m.bounds, aspect),
+            }
+        })
+        .collect::<Vec<_>>()
+}
+
+/// Angle the instances are spread over, about the view axis.
+const INSTANCE_SPREAD: f32 = std::f32::consts::FRAC_PI_8;
+
+/// The instances of an instanced workload. Every instance is turned a bit
+/// further about the center of the mesh, so they mostly overlap but every
+/// instance has a different transform for the shaders to apply.
+fn instance_buffer(
+    workload: &suite::Workload,
+    bounds: &Bounds,
+) -> Option<instancing::InstanceBuffer> {
+    if workload.instances <= 1 {
+        return None;
+    }
+    let instances = (0..workload.instances)
+        .map(|i| {
+            let angle = INSTANCE_SPREAD * i as f32 / workload.instances as f32;
+            let transform = glm::translation(&bounds.center)
+                * glm::rotation(angle, &glm::Vec3::y())
+                * glm::translation(&-bounds.center);
+            instancing::Instance::new(&transform)
+        })
+        .collect::<Vec<_>>();
+    let mut buffer = instancing::InstanceBuffer::new();
+    buffer.fill(&instances);
+    Some(buffer)
+}
+
+/// Builds the meshes of a workload on separate threads, one for every
+/// size. Obj files have a fixed size and are only loaded once.
+fn meshes<V>(workload: &suite::Workload, sizes: &[usize]) -> Vec<BenchMesh<V>>
+where
+    V: 'static + for<'a> From<&'a vertices::All> + Send,
+{
+    let sizes = match workload.mesh.as_str() {
+        "grid" | "sphere" | "saddle" => sizes,
+        _ => &sizes[..1],
+    };
     let (sender, reciever) = std::sync::mpsc::channel();
-    for size in grid_sizes.iter() {
-        let size = size.clone();
+    for size in sizes.iter() {
+        let size = *size;
+        let workload = workload.clone();
         let s = sender.clone();
         std::thread::spawn(move || {
-            let res = grid::<V>(size);
+            let res = mesh::<V>(&workload, size);
             s.send(res).unwrap();
         });
     }
@@ -277,33 +1005,92 @@ where
         render_datas.push(data);
     }
     render_datas
-        .iter()
-        .map(|d| {
-            let mut vao = VertexArray::new_static();
-            vao.bind();
-            vao.buffer_indices(&d.1);
-            vao.new_vertex_buffer_filled("all", &d.0);
-            BenchData {
-                vao,
-                n_indices: d.1.len() * 3,
-                n_vertices: d.0.len(),
-            }
-        })
-        .collect::<Vec<_>>()
 }
 
-fn grid<V: From<vertices::PosUVNormTang>>(grid_size: usize) -> (Vec<V>, Vec<pgeom::types::Face>) {
-    let (vertices, faces) = pgeom::grid(grid_size as _, grid_size as _);
-    let vertices = vertices
-        .iter()
-        .map(|v| {
-            V::from(vertices::PosUVNormTang {
+/// Distance between the stacked layers, towards the camera.
+const LAYER_SPACING: f32 = 1e-3;
+
+fn mesh<V>(workload: &suite::Workload, size: usize) -> BenchMesh<V>
+where
+    V: for<'a> From<&'a vertices::All>,
+{
+    let (mut vertices, mut faces) = base_mesh(&workload.mesh, size);
+    let bounds = Bounds::of(&vertices);
+
+    // Later layers are closer to the camera, so drawn in order every
+    // layer passes the depth test.
+    let n_vertices = vertices.len();
+    let n_faces = faces.len();
+    for layer in 1..workload.layers {
+        let offset = (n_vertices * layer) as u32;
+        for i in 0..n_vertices {
+            let mut v = vertices[i].clone();
+            v.position[1] += layer as f32 * LAYER_SPACING;
+            vertices.push(v);
+        }
+        for i in 0..n_faces {
+            let f = faces[i];
+            faces.push([f[0] + offset, f[1] + offset, f[2] + offset]);
+        }
+    }
+
+    // As in the app, so the rotor encodings interpolate within one
+    // hemisphere.
+    let mut vertices = vertices
+        .into_iter()
+        .map(vertices::All::from)
+        .collect::<Vec<_>>();
+    let seams = antipodal::align(&mut vertices, &faces);
+    antipodal::split_seams(&mut vertices, &mut faces, &seams);
+
+    BenchMesh {
+        vertices: vertices.iter().map(V::from).collect(),
+        faces,
+        bounds,
+    }
+}
+
+/// One of the procedural meshes at `size` by `size` vertices, or an obj
+/// file with generated tangents.
+fn base_mesh(name: &str, size: usize) -> (Vec<vertices::PosUVNormTang>, Vec<pgeom::types::Face>) {
+    let shape = match name {
+        "grid" => Some(pgeom::grid(size as _, size as _)),
+        "sphere" => Some(pgeom::sphere(size as _, size as _)),
+        "saddle" => Some(pgeom::monkey_saddle(size as _, size as _)),
+        _ => None,
+    };
+    if let Some((vertices, faces)) = shape {
+        let vertices = vertices
+            .iter()
+            .map(|v| vertices::PosUVNormTang {
                 position: v.position,
                 normal: v.normal,
                 uv: v.uv,
                 tangent: v.tangent,
+                handedness: 1.,
             })
-        })
-        .collect::<Vec<_>>();
+            .collect::<Vec<_>>();
+        return (vertices, faces.to_vec());
+    }
+
+    let meshes = pgeom::obj::load(name).expect("Could not load obj file");
+    let mut vertices = Vec::new();
+    let mut faces = Vec::new();
+    for mesh in meshes.iter() {
+        let (mut v, mut f) = mesh.render_data(|v| vertices::PosUVNormTang {
+            position: v.position,
+            uv: v.uv.unwrap_or_default(),
+            normal: v.normal.unwrap_or_default(),
+            tangent: [0., 0., 0.],
+            handedness: 1.,
+        });
+        tangents::generate(&mut v, &mut f);
+        let offset = vertices.len() as u32;
+        faces.extend(
+            f.iter()
+                .map(|f| [f[0] + offset, f[1] + offset, f[2] + offset]),
+        );
+        vertices.extend(v);
+    }
     (vertices, faces)
 }
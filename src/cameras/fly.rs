@@ -19,11 +19,16 @@ impl Flying {
     const FORWARD_SENSITIVITY: f32 = 6.0;
 
     pub fn new() -> Self {
+        Self::at([0., 0., 4.].into())
+    }
+
+    /// Starts at `pos`, looking down the negative z-axis.
+    pub fn at(pos: glm::Vec3) -> Self {
         Self {
             projection: glm::Mat4::identity(),
             view: glm::Mat4::identity(),
             view_projection: glm::Mat4::identity(),
-            pos: [0., 0., 4.].into(),
+            pos,
             vertical_angle: 0.0,
             horizontal_angle: 0.0,
             view_dir: -glm::Vec3::z(),
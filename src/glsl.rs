@@ -0,0 +1,192 @@
+//! Offline checks of the shaders. [`expand`] turns a shader file into the
+//! sources of its stages the way `pgl` does when it loads the shader: the
+//! file is split at its `#type` lines, every `#include` is replaced by the
+//! header it names (relative to the directory of the shader) and the
+//! defines of a stage are added after its `#version` line. [`check`] runs
+//! those sources through glslang, so broken shaders are found by
+//! `cargo test` (see `tests/shaders.rs`) instead of at startup.
+//!
+//! Headers get the attribute blocks of the current vertex layouts in their
+//! generated section, see `layout::with_glsl_blocks`. [`inline`] does the
+//! same for the shaders loaded at runtime.
+
+use crate::layout;
+use std::path::Path;
+
+/// Headers nested deeper than this are taken to include themselves.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    Vertex,
+    Fragment,
+}
+
+impl Stage {
+    /// By the name used on the `#type` lines.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "vertex" => Some(Stage::Vertex),
+            "fragment" => Some(Stage::Fragment),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Vertex => "vertex",
+            Stage::Fragment => "fragment",
+        }
+    }
+
+    fn glslang(&self) -> glslang::ShaderStage {
+        match self {
+            Stage::Vertex => glslang::ShaderStage::Vertex,
+            Stage::Fragment => glslang::ShaderStage::Fragment,
+        }
+    }
+}
+
+/// The source of every stage in the shader at `path`, in file order.
+pub fn expand(
+    path: impl AsRef<Path>,
+    vs_defines: &[String],
+    fs_defines: &[String],
+) -> Result<Vec<(Stage, String)>, String> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut stages: Vec<(Stage, String)> = Vec::new();
+    for line in source.lines() {
+        if let Some(name) = line.trim().strip_prefix("#type") {
+            let stage = Stage::from_name(name.trim())
+                .ok_or_else(|| format!("Unknown #type {} in {}", name.trim(), path.display()))?;
+            stages.push((stage, String::new()));
+            continue;
+        }
+        // Lines before the first section are dropped.
+        if let Some((_, code)) = stages.last_mut() {
+            code.push_str(line);
+            code.push('\n');
+        }
+    }
+    if stages.is_empty() {
+        return Err(format!("{} has no #type sections", path.display()));
+    }
+
+    stages
+        .into_iter()
+        .map(|(stage, code)| {
+            let defines = match stage {
+                Stage::Vertex => vs_defines,
+                Stage::Fragment => fs_defines,
+            };
+            let code = add_defines(&code, defines);
+            let code = expand_includes(&code, dir, 0)?;
+            Ok((stage, code))
+        })
+        .collect()
+}
+
+/// The shader at `path` with its includes expanded, its `#type` lines are
+/// kept so `pgl` can load it.
+pub fn inline(path: impl AsRef<Path>) -> Result<String, String> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    expand_includes(&source, dir, 0)
+}
+
+/// Adds a `#define` for every define right after the `#version` line.
+fn add_defines(code: &str, defines: &[String]) -> String {
+    let mut out = String::new();
+    for line in code.lines() {
+        out.push_str(line);
+        out.push('\n');
+        if line.trim().starts_with("#version") {
+            for define in defines.iter() {
+                out.push_str(&format!("#define {}\n", define));
+            }
+        }
+    }
+    out
+}
+
+fn expand_includes(code: &str, dir: &Path, depth: usize) -> Result<String, String> {
+    if depth > MAX_INCLUDE_DEPTH {
+        return Err("Includes nested too deep, does a header include itself?".to_string());
+    }
+    let mut out = String::new();
+    for line in code.lines() {
+        match line.trim().strip_prefix("#include") {
+            Some(include) => {
+                let include = include
+                    .trim()
+                    .trim_matches(|c| c == '"' || c == '<' || c == '>');
+                let path = dir.join(include);
+                let header = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("Could not include {}: {}", path.display(), e))?;
+                let header = layout::with_glsl_blocks(&header);
+                out.push_str(&expand_includes(&header, dir, depth + 1)?);
+            }
+            None => {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Parses the source of a stage with glslang and returns its info log when
+/// that fails.
+pub fn validate(stage: Stage, source: &str) -> Result<(), String> {
+    let compiler = glslang::Compiler::acquire().expect("Could not initialize glslang");
+    let source = glslang::ShaderSource::from(source);
+    let options = glslang::CompilerOptions {
+        // Plain GLSL as the driver gets it, not SPIR-V.
+        target: glslang::Target::None(None),
+        ..Default::default()
+    };
+    let input = glslang::ShaderInput::new(
+        &source,
+        stage.glslang(),
+        &options,
+        None::<&[(&str, Option<&str>)]>,
+        None,
+    )
+    .map_err(|e| e.to_string())?;
+    compiler
+        .create_shader(input)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Expands the shader at `path` and validates all of its stages. The error
+/// names the shader, the stage and the defines, followed by the log.
+pub fn check(
+    path: impl AsRef<Path>,
+    vs_defines: &[String],
+    fs_defines: &[String],
+) -> Result<(), String> {
+    let path = path.as_ref();
+    for (stage, source) in expand(path, vs_defines, fs_defines)? {
+        validate(stage, &source).map_err(|log| {
+            let defines = match stage {
+                Stage::Vertex => vs_defines,
+                Stage::Fragment => fs_defines,
+            };
+            format!(
+                "{} ({} stage, defines [{}]):\n{}",
+                path.display(),
+                stage.name(),
+                defines.join(", "),
+                log
+            )
+        })?;
+    }
+    Ok(())
+}
@@ -0,0 +1,361 @@
+//! Imports glTF 2.0 files (.gltf and .glb). Unlike the obj files, glTF
+//! carries the tangents an asset was authored with and the normal map of
+//! every primitive, so the encodings can be compared against reference
+//! tangents. Every triangle primitive becomes an `app::Mesh` with the
+//! transform of its node and its material, with the base color, normal and
+//! roughness textures.
+//! Tangents are only generated (see `tangents`) for primitives without
+//! them. Scaling, shearing and mirroring in the node transform are baked
+//! into the vertices, as the motor encodings only carry rigid transforms.
+
+use crate::app::Mesh;
+use crate::{antipodal, tangents, vertices};
+use std::path::Path;
+use std::sync::Arc;
+
+/// A decoded texture in RGBA, 8 bits per channel. Images are decoded on
+/// the loading thread and uploaded once the mesh reaches the main thread.
+pub struct Image {
+    /// Unique per file and image, textures shared by primitives are only
+    /// uploaded once.
+    pub key: String,
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// A single pixel of `rgba`.
+    pub fn solid(key: &str, rgba: [u8; 4]) -> Self {
+        Self {
+            key: key.into(),
+            width: 1,
+            height: 1,
+            pixels: rgba.to_vec(),
+        }
+    }
+
+    fn from_gltf(key: String, data: &gltf::image::Data) -> Self {
+        use gltf::image::Format;
+        let (channels, bytes) = match data.format {
+            Format::R8 => (1, 1),
+            Format::R8G8 => (2, 1),
+            Format::R8G8B8 | Format::B8G8R8 => (3, 1),
+            Format::R8G8B8A8 | Format::B8G8R8A8 => (4, 1),
+            Format::R16 => (1, 2),
+            Format::R16G16 => (2, 2),
+            Format::R16G16B16 => (3, 2),
+            Format::R16G16B16A16 => (4, 2),
+        };
+        let bgr = matches!(data.format, Format::B8G8R8 | Format::B8G8R8A8);
+        let mut pixels = Vec::with_capacity((data.width * data.height * 4) as usize);
+        for pixel in data.pixels.chunks_exact(channels * bytes) {
+            // The most significant byte of a channel, 16 bit channels are
+            // little endian.
+            let channel = |i: usize| match i {
+                i if i < channels => pixel[i * bytes + bytes - 1],
+                3 => 255,
+                _ => 0,
+            };
+            let rgba = [channel(0), channel(1), channel(2), channel(3)];
+            if bgr {
+                pixels.extend_from_slice(&[rgba[2], rgba[1], rgba[0], rgba[3]]);
+            } else {
+                pixels.extend_from_slice(&rgba);
+            }
+        }
+        Self {
+            key,
+            width: data.width,
+            height: data.height,
+            pixels,
+        }
+    }
+
+    /// Uploads the image with mipmaps, must run on the thread with the GL
+    /// context.
+    pub fn upload(&self) -> Texture {
+        let mut id = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_2D, id);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_MIN_FILTER,
+                gl::LINEAR_MIPMAP_LINEAR as i32,
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA8 as i32,
+                self.width as i32,
+                self.height as i32,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                self.pixels.as_ptr() as *const _,
+            );
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+        }
+        Texture { id }
+    }
+}
+
+/// An uploaded [`Image`]. glTF puts the first row of an image at v = 0, so
+/// unlike the textures loaded with `pgl` it is not flipped.
+pub struct Texture {
+    id: gl::types::GLuint,
+}
+
+impl Texture {
+    pub fn bind_to(&self, slot: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + slot);
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+        }
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteTextures(1, &self.id) }
+    }
+}
+
+/// The material of a primitive, sent along with its mesh. The app makes it
+/// a `material::Material` once the images are uploaded.
+pub struct MeshMaterial {
+    /// The base color factor.
+    pub albedo: [f32; 4],
+    pub albedo_map: Option<Arc<Image>>,
+    pub normal_map: Option<Arc<Image>>,
+    /// The metallic roughness texture, its green channel is the roughness.
+    pub roughness_map: Option<Arc<Image>>,
+}
+
+/// Loads the meshes of the default scene (or the first one) in the file
+/// at `path`, all in `group`.
+pub fn load(path: impl AsRef<Path>, group: &str) -> Result<Vec<Mesh>, String> {
+    let path = path.as_ref();
+    let (document, buffers, images) =
+        gltf::import(path).map_err(|e| format!("Could not load {}: {}", path.display(), e))?;
+    let images = images
+        .iter()
+        .enumerate()
+        .map(|(i, data)| Arc::new(Image::from_gltf(format!("{}#{}", path.display(), i), data)))
+        .collect::<Vec<_>>();
+    let scene = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .ok_or_else(|| format!("{} has no scene", path.display()))?;
+
+    let mut meshes = Vec::new();
+    for node in scene.nodes() {
+        load_node(
+            &node,
+            &glm::Mat4::identity(),
+            &buffers,
+            &images,
+            group,
+            &mut meshes,
+        );
+    }
+    Ok(meshes)
+}
+
+/// Adds the primitives of the node and its children to `meshes`.
+fn load_node(
+    node: &gltf::Node,
+    parent: &glm::Mat4,
+    buffers: &[gltf::buffer::Data],
+    images: &[Arc<Image>],
+    group: &str,
+    meshes: &mut Vec<Mesh>,
+) {
+    let local: [[f32; 4]; 4] = node.transform().matrix();
+    let transform = parent * glm::Mat4::from(local);
+
+    if let Some(mesh) = node.mesh() {
+        let name = node
+            .name()
+            .or_else(|| mesh.name())
+            .unwrap_or("Unnamed glTF Mesh");
+        for (i, primitive) in mesh.primitives().enumerate() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                continue;
+            }
+            let name = if mesh.primitives().len() > 1 {
+                format!("{} {}", name, i)
+            } else {
+                name.to_string()
+            };
+            if let Some(mesh) = load_primitive(&primitive, buffers, images, name, group, transform)
+            {
+                meshes.push(mesh);
+            }
+        }
+    }
+    for child in node.children() {
+        load_node(&child, &transform, buffers, images, group, meshes);
+    }
+}
+
+/// None when the primitive has no positions.
+fn load_primitive(
+    primitive: &gltf::Primitive,
+    buffers: &[gltf::buffer::Data],
+    images: &[Arc<Image>],
+    name: String,
+    group: &str,
+    transform: glm::Mat4,
+) -> Option<Mesh> {
+    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+    let positions = reader.read_positions()?.collect::<Vec<_>>();
+    let n = positions.len();
+    let uvs = reader
+        .read_tex_coords(0)
+        .map(|uvs| uvs.into_f32().collect())
+        .unwrap_or_else(|| vec![[0.; 2]; n]);
+    let normals = reader
+        .read_normals()
+        .map(|normals| normals.collect::<Vec<_>>());
+    // glTF asks to ignore the tangents of a primitive without normals.
+    let authored_tangents = match normals {
+        Some(_) => reader.read_tangents().map(|t| t.collect::<Vec<_>>()),
+        None => None,
+    };
+    let mut faces = match reader.read_indices() {
+        Some(indices) => indices
+            .into_u32()
+            .collect::<Vec<_>>()
+            .chunks_exact(3)
+            .map(|f| [f[0], f[1], f[2]])
+            .collect::<Vec<_>>(),
+        None => (0..n as u32 / 3)
+            .map(|f| [3 * f, 3 * f + 1, 3 * f + 2])
+            .collect(),
+    };
+
+    let (transform, rest) = split_rigid(&transform);
+    let mut vertices = (0..n)
+        .map(|i| {
+            let tangent = authored_tangents
+                .as_ref()
+                .map_or([0., 0., 0., 1.], |t| t[i]);
+            vertices::PosUVNormTang {
+                position: positions[i],
+                uv: uvs[i],
+                normal: normals.as_ref().map_or([0.; 3], |n| n[i]),
+                tangent: [tangent[0], tangent[1], tangent[2]],
+                handedness: tangent[3],
+            }
+        })
+        .collect::<Vec<_>>();
+    if normals.is_none() {
+        vertices = flat_normals(&vertices, &mut faces);
+    }
+    bake(&rest, &mut vertices, &mut faces);
+    if authored_tangents.is_none() {
+        tangents::generate(&mut vertices, &mut faces);
+    }
+    let mut vertices = vertices
+        .into_iter()
+        .map(vertices::All::from)
+        .collect::<Vec<_>>();
+    let seams = antipodal::align(&mut vertices, &faces);
+    antipodal::split_seams(&mut vertices, &mut faces, &seams);
+
+    let material = primitive.material();
+    let pbr = material.pbr_metallic_roughness();
+    let image = |texture: gltf::texture::Texture| images[texture.source().index()].clone();
+    Some(Mesh {
+        vertices,
+        faces,
+        name,
+        group: group.into(),
+        active: false,
+        transform,
+        material: None,
+        file_material: Some(MeshMaterial {
+            albedo: pbr.base_color_factor(),
+            albedo_map: pbr.base_color_texture().map(|info| image(info.texture())),
+            normal_map: material.normal_texture().map(|info| image(info.texture())),
+            roughness_map: pbr
+                .metallic_roughness_texture()
+                .map(|info| image(info.texture())),
+        }),
+    })
+}
+
+/// Gives every face its own vertices with the normal of the face, which is
+/// what glTF asks for primitives without normals. Degenerate faces get +z.
+fn flat_normals(
+    vertices: &[vertices::PosUVNormTang],
+    faces: &mut [[u32; 3]],
+) -> Vec<vertices::PosUVNormTang> {
+    let mut flat = Vec::with_capacity(faces.len() * 3);
+    for face in faces.iter_mut() {
+        let p = |k: usize| glm::Vec3::from(vertices[face[k] as usize].position);
+        let normal = (p(1) - p(0))
+            .cross(&(p(2) - p(0)))
+            .try_normalize(0.)
+            .unwrap_or_else(glm::Vec3::z);
+        for index in face.iter_mut() {
+            let mut v = vertices[*index as usize].clone();
+            v.normal = normal.into();
+            *index = flat.len() as u32;
+            flat.push(v);
+        }
+    }
+    flat
+}
+
+/// Splits a transform into a rigid transform and the linear map applied
+/// before it, `transform = rigid * rest`. The rotation is found with
+/// Gram-Schmidt on the columns, so `rest` is upper triangular and holds
+/// the scaling, shearing and mirroring.
+fn split_rigid(transform: &glm::Mat4) -> (glm::Mat4, glm::Mat3) {
+    let linear = glm::mat4_to_mat3(transform);
+    let column = |i| glm::column(&linear, i);
+    let x = column(0).normalize();
+    let y = (column(1) - x * x.dot(&column(1))).normalize();
+    let z = x.cross(&y);
+    let rotation = glm::Mat3::from_columns(&[x, y, z]);
+    let mut rigid = glm::mat3_to_mat4(&rotation);
+    rigid.set_column(3, &glm::column(transform, 3));
+    (rigid, rotation.transpose() * linear)
+}
+
+/// Applies the linear map to the vertices: positions and tangents by the
+/// map, normals by its inverse transpose. A mirroring map flips the
+/// handedness and the winding of the faces.
+fn bake(rest: &glm::Mat3, vertices: &mut [vertices::PosUVNormTang], faces: &mut [[u32; 3]]) {
+    const RIGID_EPSILON: f32 = 1e-5;
+    if (rest - glm::Mat3::identity()).abs().max() < RIGID_EPSILON {
+        return;
+    }
+    let normal_matrix = glm::inverse_transpose(*rest);
+    let apply = |m: &glm::Mat3, v: [f32; 3]| -> [f32; 3] { (m * glm::Vec3::from(v)).into() };
+    let mirrored = rest.determinant() < 0.;
+    for v in vertices.iter_mut() {
+        v.position = apply(rest, v.position);
+        v.normal = glm::Vec3::from(apply(&normal_matrix, v.normal))
+            .normalize()
+            .into();
+        v.tangent = glm::Vec3::from(apply(rest, v.tangent))
+            .try_normalize(0.)
+            .map_or(v.tangent, Into::into);
+        if mirrored {
+            v.handedness = -v.handedness;
+        }
+    }
+    if mirrored {
+        for face in faces.iter_mut() {
+            face.swap(1, 2);
+        }
+    }
+}
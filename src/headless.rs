@@ -0,0 +1,157 @@
+//! An OpenGL context without a window, for running the benchmarks on
+//! machines without a display (like CI). Uses an EGL context on Mesa's
+//! surfaceless platform, which also works with the llvmpipe software
+//! rasterizer, and renders into a framebuffer object of the requested size
+//! instead of a window surface.
+
+use khronos_egl as egl;
+
+/// `EGL_PLATFORM_SURFACELESS_MESA`, not exported by the egl crate.
+const PLATFORM_SURFACELESS_MESA: egl::Enum = 0x31DD;
+
+pub struct HeadlessContext {
+    egl: egl::Instance<egl::Static>,
+    display: egl::Display,
+    context: egl::Context,
+    framebuffer: u32,
+    renderbuffers: [u32; 2],
+    width: usize,
+    height: usize,
+}
+
+impl HeadlessContext {
+    /// Creates an OpenGL 3.3 core context, makes it current, loads the
+    /// OpenGL functions and binds a `width` by `height` framebuffer with a
+    /// color and depth attachment.
+    pub fn new(width: usize, height: usize) -> Self {
+        let egl = egl::Instance::new(egl::Static);
+        let display = egl
+            .get_platform_display(
+                PLATFORM_SURFACELESS_MESA,
+                egl::DEFAULT_DISPLAY,
+                &[egl::ATTRIB_NONE],
+            )
+            .expect("Could not get a surfaceless EGL display");
+        egl.initialize(display)
+            .expect("Could not initialize the EGL display");
+        egl.bind_api(egl::OPENGL_API)
+            .expect("EGL does not support OpenGL");
+
+        let config = egl
+            .choose_first_config(display, &[egl::RENDERABLE_TYPE, egl::OPENGL_BIT, egl::NONE])
+            .expect("Could not choose an EGL config")
+            .expect("No EGL config supports OpenGL");
+        let context = egl
+            .create_context(
+                display,
+                config,
+                None,
+                &[
+                    egl::CONTEXT_MAJOR_VERSION,
+                    3,
+                    egl::CONTEXT_MINOR_VERSION,
+                    3,
+                    egl::CONTEXT_OPENGL_PROFILE_MASK,
+                    egl::CONTEXT_OPENGL_CORE_PROFILE_BIT,
+                    egl::NONE,
+                ],
+            )
+            .expect("Could not create an OpenGL 3.3 context");
+        egl.make_current(display, None, None, Some(context))
+            .expect("Could not make the context current");
+
+        // Same function pointers as the ones pgl uses, see Cargo.toml.
+        gl::load_with(|name| {
+            egl.get_proc_address(name)
+                .map_or(std::ptr::null(), |f| f as *const _)
+        });
+
+        let (framebuffer, renderbuffers) = create_framebuffer(width, height);
+        Self {
+            egl,
+            display,
+            context,
+            framebuffer,
+            renderbuffers,
+            width,
+            height,
+        }
+    }
+
+    /// Replaces the framebuffer with one of the new size.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.delete_framebuffer();
+        let (framebuffer, renderbuffers) = create_framebuffer(width, height);
+        self.framebuffer = framebuffer;
+        self.renderbuffers = renderbuffers;
+        self.width = width;
+        self.height = height;
+    }
+
+    fn delete_framebuffer(&self) {
+        unsafe {
+            gl::DeleteRenderbuffers(2, self.renderbuffers.as_ptr());
+            gl::DeleteFramebuffers(1, &self.framebuffer);
+        }
+    }
+
+    pub fn framebuffer_size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn aspect(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+}
+
+impl Drop for HeadlessContext {
+    fn drop(&mut self) {
+        self.delete_framebuffer();
+        self.egl.make_current(self.display, None, None, None).ok();
+        self.egl.destroy_context(self.display, self.context).ok();
+        self.egl.terminate(self.display).ok();
+    }
+}
+
+/// Creates and binds a framebuffer with a color and depth attachment.
+fn create_framebuffer(width: usize, height: usize) -> (u32, [u32; 2]) {
+    let mut framebuffer = 0;
+    let mut renderbuffers = [0; 2];
+    unsafe {
+        gl::GenFramebuffers(1, &mut framebuffer);
+        gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+        gl::GenRenderbuffers(2, renderbuffers.as_mut_ptr());
+
+        gl::BindRenderbuffer(gl::RENDERBUFFER, renderbuffers[0]);
+        gl::RenderbufferStorage(gl::RENDERBUFFER, gl::RGBA8, width as _, height as _);
+        gl::FramebufferRenderbuffer(
+            gl::FRAMEBUFFER,
+            gl::COLOR_ATTACHMENT0,
+            gl::RENDERBUFFER,
+            renderbuffers[0],
+        );
+
+        gl::BindRenderbuffer(gl::RENDERBUFFER, renderbuffers[1]);
+        gl::RenderbufferStorage(
+            gl::RENDERBUFFER,
+            gl::DEPTH24_STENCIL8,
+            width as _,
+            height as _,
+        );
+        gl::FramebufferRenderbuffer(
+            gl::FRAMEBUFFER,
+            gl::DEPTH_STENCIL_ATTACHMENT,
+            gl::RENDERBUFFER,
+            renderbuffers[1],
+        );
+
+        let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+        assert_eq!(
+            status,
+            gl::FRAMEBUFFER_COMPLETE,
+            "Offscreen framebuffer is incomplete"
+        );
+        gl::Viewport(0, 0, width as _, height as _);
+    }
+    (framebuffer, renderbuffers)
+}
@@ -0,0 +1,113 @@
+//! Watches shader files, so the app can recompile its shaders while it
+//! runs. A shader depends on its own file and on every header it
+//! `#include`s, recursively, with the include paths relative to the
+//! directory of the shader. The modification times are polled, which is
+//! cheap for the handful of files involved.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Time between two looks at the files.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+struct WatchedShader {
+    path: PathBuf,
+    /// Every file the shader is built from, with its modification time.
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl WatchedShader {
+    fn new(path: PathBuf) -> Self {
+        let files = dependencies(&path)
+            .into_iter()
+            .map(|file| {
+                let modified = modified(&file);
+                (file, modified)
+            })
+            .collect();
+        Self { path, files }
+    }
+
+    fn changed(&self) -> bool {
+        self.files
+            .iter()
+            .any(|(file, modified)| self::modified(file) != *modified)
+    }
+}
+
+pub struct ShaderWatcher {
+    shaders: Vec<WatchedShader>,
+    last_poll: Instant,
+}
+
+impl ShaderWatcher {
+    pub fn new() -> Self {
+        Self {
+            shaders: Vec::new(),
+            last_poll: Instant::now(),
+        }
+    }
+
+    /// Starts watching the shader at `path`, a shader is watched once.
+    pub fn watch(&mut self, path: impl AsRef<Path>) {
+        let path = path.as_ref().to_path_buf();
+        if !self.shaders.iter().any(|s| s.path == path) {
+            self.shaders.push(WatchedShader::new(path));
+        }
+    }
+
+    /// The shaders of which a file changed since the last poll. Their
+    /// includes are read again, as those may have changed too.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return Vec::new();
+        }
+        self.last_poll = Instant::now();
+
+        let mut changed = Vec::new();
+        for shader in self.shaders.iter_mut() {
+            if shader.changed() {
+                *shader = WatchedShader::new(shader.path.clone());
+                changed.push(shader.path.clone());
+            }
+        }
+        changed
+    }
+}
+
+impl Default for ShaderWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The shader file itself followed by all the files it includes.
+pub fn dependencies(path: &Path) -> Vec<PathBuf> {
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut files = vec![path.to_path_buf()];
+    let mut i = 0;
+    while i < files.len() {
+        // Files that can not be read are still watched, they may appear.
+        let source = std::fs::read_to_string(&files[i]).unwrap_or_default();
+        for include in includes(&source) {
+            let include = dir.join(include);
+            if !files.contains(&include) {
+                files.push(include);
+            }
+        }
+        i += 1;
+    }
+    files
+}
+
+/// The paths of the `#include` lines in a shader.
+fn includes(source: &str) -> impl Iterator<Item = &str> {
+    source.lines().filter_map(|line| {
+        let path = line.trim().strip_prefix("#include")?.trim();
+        Some(path.trim_matches(|c| c == '"' || c == '<' || c == '>'))
+    })
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).ok()?.modified().ok()
+}
@@ -1,10 +1,27 @@
-use crate::material::Material;
-use crate::{app, cameras, time};
+use crate::material::TextureHandle;
+use crate::{app, cameras, shaders, techniques, time};
 use pgl::window::Key;
 
-/// Lets you change the material params on every object in the scene
-pub fn material_editor(ui: &mut imgui::Ui, material: &mut Material) {
+/// Lets you change the material of the selected model, its params and
+/// which scene textures it uses. It can be copied to the whole group.
+pub fn material_editor(ui: &mut imgui::Ui, state: &mut app::State, scene: &mut app::Scene) {
     imgui::Window::new(imgui::im_str!("Material")).build(ui, || {
+        if scene.models.is_empty() {
+            return;
+        }
+        let names = scene
+            .models
+            .iter()
+            .map(|m| m.name.as_str())
+            .collect::<Vec<_>>();
+        imgui::ComboBox::new(imgui::im_str!("Model")).build_simple(
+            ui,
+            &mut state.selected_model,
+            &names,
+            &get_name,
+        );
+        let model = &mut scene.models[state.selected_model];
+        let material = &mut model.material;
         imgui::Slider::new(imgui::im_str!("Reflectiveness"))
             .range(1..=100)
             .build(ui, &mut material.reflectiveness);
@@ -15,9 +32,70 @@ pub fn material_editor(ui: &mut imgui::Ui, material: &mut Material) {
             .range(0.0..=1.0)
             .build(ui, &mut material.specular);
         imgui::ColorEdit::new(imgui::im_str!("Albedo"), &mut material.albedo).build(&ui);
+        let textures = &scene.textures;
+        let picker = |label: &imgui::ImStr, map: &mut Option<TextureHandle>, kind| {
+            texture_picker(ui, label, map, textures, kind)
+        };
+        picker(
+            imgui::im_str!("Albedo Map"),
+            &mut material.albedo_map,
+            techniques::Texture::Albedo,
+        );
+        picker(
+            imgui::im_str!("Normal Map"),
+            &mut material.normal_map,
+            techniques::Texture::NormalMap,
+        );
+        picker(
+            imgui::im_str!("Roughness Map"),
+            &mut material.roughness_map,
+            techniques::Texture::Roughness,
+        );
+        if ui.button(imgui::im_str!("Apply To Group")) {
+            let material = model.material.clone();
+            let group = model.group.clone();
+            for m in scene.models.iter_mut().filter(|m| m.group == group) {
+                m.material = material.clone();
+            }
+        }
     });
 }
 
+/// Picks one of the scene textures of `kind` for a map of a material. A map
+/// that is not a scene texture, like one from a glTF file, is shown as Own.
+fn texture_picker(
+    ui: &imgui::Ui,
+    label: &imgui::ImStr,
+    map: &mut Option<TextureHandle>,
+    textures: &[app::SceneTexture],
+    kind: techniques::Texture,
+) {
+    let textures = textures
+        .iter()
+        .filter(|t| t.kind == kind)
+        .collect::<Vec<_>>();
+    let mut names = vec!["None"];
+    names.extend(textures.iter().map(|t| t.name.as_str()));
+    let mut current = match map {
+        None => 0,
+        Some(handle) => match textures.iter().position(|t| t.texture.same(handle)) {
+            Some(i) => i + 1,
+            None => {
+                names.push("Own");
+                names.len() - 1
+            }
+        },
+    };
+    if imgui::ComboBox::new(label).build_simple(ui, &mut current, &names, &get_name) {
+        // Picking Own again keeps the map.
+        if current == 0 {
+            *map = None;
+        } else if let Some(texture) = textures.get(current - 1) {
+            *map = Some(texture.texture.clone());
+        }
+    }
+}
+
 /// Shows some basic performance performance statistics
 pub fn performance(
     ui: &mut imgui::Ui,
@@ -28,12 +106,14 @@ pub fn performance(
 ) {
     let mut n_indices = 0;
     let mut n_vertices = 0;
+    let mut n_draws = 0;
     for model in scene.models.iter() {
         if !model.active {
             continue;
         }
         n_indices += model.n_indices;
         n_vertices += model.n_vertices;
+        n_draws += 1;
     }
     let (w, h) = window.window_size();
     let n_fragments = w * h;
@@ -46,14 +126,30 @@ pub fn performance(
         ));
         ui.text(format!(
             "Number Of Indices: {}",
-            n_indices * state.n_draws as usize
+            n_indices * state.n_instances as usize
         ));
         ui.text(format!(
             "Number Of Vertices: {}",
-            n_vertices * state.n_draws as usize
+            n_vertices * state.n_instances as usize
         ));
         ui.text(format!("Number Of Fragments: {}", n_fragments));
-        ui.text(format!("Number Of Draw Calls: {}", state.n_draws));
+        ui.text(format!("Number Of Instances: {}", state.n_instances));
+        ui.text(format!("Number Of Draw Calls: {}", n_draws));
+    });
+}
+
+/// Shows the compile logs of the shaders that failed to reload, the app
+/// keeps drawing with their old programs.
+pub fn shader_errors(ui: &mut imgui::Ui, errors: &[(String, String)]) {
+    if errors.is_empty() {
+        return;
+    }
+    imgui::Window::new(imgui::im_str!("Shader Errors")).build(ui, || {
+        for (name, log) in errors.iter() {
+            ui.text_colored([1., 0.3, 0.3, 1.], name);
+            ui.text_wrapped(&imgui::ImString::new(log.as_str()));
+            ui.separator();
+        }
     });
 }
 
@@ -73,7 +169,7 @@ pub fn main_options(ui: &mut imgui::Ui, state: &mut app::State, scene: &mut app:
             .build(&ui, &mut state.model_rotation_x);
         imgui::Slider::new(imgui::im_str!("Number Of Geometries"))
             .range(1..=40)
-            .build(ui, &mut state.n_draws);
+            .build(ui, &mut state.n_instances);
 
         // finds all model groups there are and gives an active flag if one
         // is active and deactive flag if one is not active.
@@ -155,38 +251,24 @@ pub fn main_options(ui: &mut imgui::Ui, state: &mut app::State, scene: &mut app:
     });
 }
 
-/// Lets you change textures and shaders used on the models in the scene
+/// Lets you change the shaders used on the models in the scene
 pub fn shading(ui: &mut imgui::Ui, state: &mut app::State) {
     imgui::Window::new(imgui::im_str!("Shading")).build(ui, || {
+        let labels = techniques::in_app().map(|t| t.label).collect::<Vec<_>>();
         imgui::ListBox::new(imgui::im_str!("Shader")).build_simple(
             ui,
             &mut state.shader,
-            &[
-                "Flat Phong Shading",
-                "Albedo Mapping",
-                "Normal Mapping With Matrix",
-                "Normal And Albedo Mapping",
-                "Normal Mapping With Rotor",
-                "Normal Mapping With Motor",
-                "Normal Mapping With Outer Log Motor",
-                "Normal Mapping With Outer Log Rotor",
-                "Normal Mapping With QTangent",
-                "Normal Mapping With Tang and BiTang",
-                "With Cayley Motor",
-                "With Cayley Rotor",
-            ],
-            &get_name,
-        );
-        imgui::ListBox::new(imgui::im_str!("Texture")).build_simple(
-            ui,
-            &mut state.texture,
-            &["World", "Wall"],
+            &labels,
             &get_name,
         );
-        imgui::ListBox::new(imgui::im_str!("Normal Map")).build_simple(
+        let labels = shaders::DecodePlacement::ALL
+            .iter()
+            .map(shaders::DecodePlacement::label)
+            .collect::<Vec<_>>();
+        imgui::ListBox::new(imgui::im_str!("Decode")).build_simple(
             ui,
-            &mut state.normal_map,
-            &["Bricks", "Wall"],
+            &mut state.decode_placement,
+            &labels,
             &get_name,
         );
     });
@@ -0,0 +1,106 @@
+//! Instanced drawing. Every instance has a transform in the model space of
+//! the mesh, which the shaders compiled with [`DEFINE`] read from the
+//! `Instances` uniform block at `gl_InstanceID`: as a motor that the motor
+//! encodings compose with the tangent motor of the vertex (see
+//! `shaders/nm_motor.glsl`), and as a matrix for the others. Keeping the
+//! instances out of the vertex attributes leaves all attribute locations to
+//! the vertex layouts.
+
+use crate::shaders;
+use pgl::buffer::{Buffer, BufferType, DrawType};
+use pgl::shader::ShaderProgram;
+
+/// Selects the instance block in the shaders.
+pub const DEFINE: &str = "INSTANCED";
+
+/// Instances in the block, as `MAX_INSTANCES` in `shaders/headers/app.glsl`.
+/// At 96 bytes each they stay within the 16 KiB every uniform block may
+/// take.
+pub const MAX_INSTANCES: usize = 128;
+
+/// Matches `struct Instance` in `shaders/headers/app.glsl`, std140.
+#[repr(C)]
+pub struct Instance {
+    /// p1 and p2, as for the model motor.
+    pub motor: [[f32; 4]; 2],
+    pub matrix: [[f32; 4]; 4],
+}
+
+impl Instance {
+    /// The transform should be rigid, the motor drops scaling and the
+    /// shaders use the matrix for normals as well.
+    pub fn new(transform: &glm::Mat4) -> Self {
+        Self {
+            motor: shaders::model_motor(transform),
+            matrix: (*transform).into(),
+        }
+    }
+}
+
+/// Points the `Instances` block of the shader to the binding point of the
+/// instance buffers. The `App` block has binding point 0.
+pub fn bind_block(shader: &ShaderProgram) {
+    shader.bind_uniform_block("Instances", 1);
+}
+
+/// A uniform buffer of up to [`MAX_INSTANCES`] [`Instance`]s.
+pub struct InstanceBuffer {
+    buffer: Buffer,
+    count: usize,
+}
+
+impl InstanceBuffer {
+    /// Also makes it the buffer the shaders read, see [`InstanceBuffer::bind`].
+    pub fn new() -> Self {
+        let buffer = Buffer::new(BufferType::Uniform, DrawType::Dynamic);
+        buffer.bind();
+        buffer.init(std::mem::size_of::<[Instance; MAX_INSTANCES]>());
+        buffer.set_binding(1);
+        buffer.unbind();
+        Self { buffer, count: 0 }
+    }
+
+    /// Instances in the buffer.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn fill(&mut self, instances: &[Instance]) {
+        assert!(
+            instances.len() <= MAX_INSTANCES,
+            "{} instances, at most {} fit the instance block",
+            instances.len(),
+            MAX_INSTANCES
+        );
+        self.buffer.bind();
+        self.buffer.subbuffer(instances, 0);
+        self.count = instances.len();
+    }
+
+    /// Makes this the buffer the shaders read the instances from, needed
+    /// when there are multiple.
+    pub fn bind(&self) {
+        self.buffer.bind();
+        self.buffer.set_binding(1);
+    }
+}
+
+impl Default for InstanceBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Draws the indexed triangles of the bound vertex array `instances`
+/// times.
+pub fn draw(n_indices: usize, instances: usize) {
+    unsafe {
+        gl::DrawElementsInstanced(
+            gl::TRIANGLES,
+            n_indices as _,
+            gl::UNSIGNED_INT,
+            std::ptr::null(),
+            instances as _,
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! Vertex layouts are declared once, with [`vertex_layout!`] for the f32
+//! layouts in `vertices` and [`packed_vertex_layout!`] for the ones in
+//! `packed`. That single declaration gives the struct, the attribute list
+//! used to set up the vertex array and the `#ifdef *_ATTRIBUTES` block that
+//! the shaders select with a define. The blocks in
+//! `shaders/headers/app.glsl` are replaced by the ones of the declarations
+//! when a shader is loaded (see `glsl::inline`), a test checks that the
+//! committed copy is current, and the size of every struct is checked
+//! against its attributes at compile time, so the three can not drift
+//! apart. [`for_each_layout!`] lists every layout once, by the name used in
+//! suites.
+
+/// A vertex layout that has a matching attribute block in the shaders.
+pub trait GlslLayout {
+    /// The define that selects the attribute block.
+    const DEFINE: &'static str;
+    /// Lines added to the block after the attributes, used to give the
+    /// components of packed attributes their usual names.
+    const GLSL_EXTRA: &'static str = "";
+    /// GLSL type and name of every attribute, in location order.
+    fn glsl_attributes() -> Vec<(&'static str, &'static str)>;
+}
+
+macro_rules! glsl_type {
+    (Float) => {
+        "float"
+    };
+    (Vec2) => {
+        "vec2"
+    };
+    (Vec3) => {
+        "vec3"
+    };
+    (Vec4) => {
+        "vec4"
+    };
+}
+
+macro_rules! glsl_size {
+    (Float) => {
+        4
+    };
+    (Vec2) => {
+        8
+    };
+    (Vec3) => {
+        12
+    };
+    (Vec4) => {
+        16
+    };
+}
+
+/// Declares a vertex of f32 attributes. Every field lists the attributes it
+/// holds as `GlslDType` variant and GLSL name, a field can span multiple
+/// attribute locations. An optional string after the struct is added to the
+/// attribute block.
+///
+/// ```ignore
+/// vertex_layout! {
+///     pub struct Motor: "MOTOR_ATTRIBUTES" {
+///         pub position: [f32; 3] => [Vec3 aPos],
+///         pub motor: [[f32; 4]; 2] => [Vec4 aMotor1, Vec4 aMotor2],
+///     }
+/// }
+/// ```
+macro_rules! vertex_layout {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident : $define:literal {
+            $(
+                $(#[$field_meta:meta])*
+                pub $field:ident : $ty:ty => [ $( $dtype:ident $attr:ident ),+ ]
+            ),* $(,)?
+        }
+        $( $extra:literal )?
+    ) => {
+        $(#[$meta])*
+        #[repr(C)]
+        pub struct $name {
+            $( $(#[$field_meta])* pub $field: $ty, )*
+        }
+
+        impl pgl::vao::HasVertexAttributes for $name {
+            fn attributes() -> Vec<pgl::GlslDType> {
+                vec![ $( $( pgl::GlslDType::$dtype ),+ ),* ]
+            }
+        }
+
+        impl $crate::layout::GlslLayout for $name {
+            const DEFINE: &'static str = $define;
+            $( const GLSL_EXTRA: &'static str = $extra; )?
+            fn glsl_attributes() -> Vec<(&'static str, &'static str)> {
+                vec![ $( $( (glsl_type!($dtype), stringify!($attr)) ),+ ),* ]
+            }
+        }
+
+        // Fails to compile when the fields and the attributes differ in size.
+        const _: [(); 0 $( $( + glsl_size!($dtype) )+ )*] = [(); std::mem::size_of::<$name>()];
+    };
+}
+
+/// Implements `From<PosUVNormTang>` for a layout that is built from
+/// `vertices::All`.
+macro_rules! impl_from_pos_uv_norm_tang {
+    ($name:ty) => {
+        impl From<$crate::vertices::PosUVNormTang> for $name {
+            fn from(v: $crate::vertices::PosUVNormTang) -> Self {
+                Self::from(&$crate::vertices::All::from(v))
+            }
+        }
+    };
+}
+
+/// Declares a packed vertex. Every field is a single `PackedDType` followed
+/// by the GLSL type and name it is read as, padding fields leave those out.
+/// An optional string after the struct is added to the attribute block.
+macro_rules! packed_vertex_layout {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident : $define:literal {
+            $(
+                $(#[$field_meta:meta])*
+                $vis:vis $field:ident : $ty:ty =>
+                    [ $dtype:ident $( ($n:expr) )? $( as $glsl:ident $attr:ident )? ]
+            ),* $(,)?
+        }
+        $( $extra:literal )?
+    ) => {
+        $(#[$meta])*
+        #[repr(C)]
+        pub struct $name {
+            $( $(#[$field_meta])* $vis $field: $ty, )*
+        }
+
+        impl $crate::packed::HasPackedVertexAttributes for $name {
+            fn packed_attributes() -> Vec<$crate::packed::PackedDType> {
+                vec![ $( $crate::packed::PackedDType::$dtype $( ($n) )? ),* ]
+            }
+        }
+
+        impl $crate::layout::GlslLayout for $name {
+            const DEFINE: &'static str = $define;
+            $( const GLSL_EXTRA: &'static str = $extra; )?
+            #[allow(clippy::vec_init_then_push)]
+            fn glsl_attributes() -> Vec<(&'static str, &'static str)> {
+                let mut attributes = Vec::new();
+                $( $( attributes.push((stringify!($glsl), stringify!($attr))); )? )*
+                attributes
+            }
+        }
+
+        // Fails to compile when the fields and the attributes differ in size.
+        const _: [(); 0 $( + $crate::packed::PackedDType::$dtype $( ($n) )? .size() )*] =
+            [(); std::mem::size_of::<$name>()];
+    };
+}
+
+/// The table of every vertex layout: calls `$callback!` with a `kind
+/// "name" Type,` entry per layout, where the kind is `f32` or `packed` and
+/// the name is the one used in suites and with `--vertex`. `All` comes
+/// last, like its attribute block.
+#[macro_export]
+macro_rules! for_each_layout {
+    ($callback:ident) => {
+        $callback! {
+            f32 "normtang" $crate::vertices::PosUVNormTang,
+            f32 "matrix" $crate::vertices::Matrix,
+            f32 "rotor" $crate::vertices::Rotor,
+            f32 "outerrotor" $crate::vertices::OuterRotor,
+            f32 "cayleyrotor" $crate::vertices::CayleyRotor,
+            f32 "qrotor" $crate::vertices::QRotor,
+            f32 "motor" $crate::vertices::Motor,
+            f32 "outermotor" $crate::vertices::OuterMotor,
+            f32 "cayleymotor" $crate::vertices::CayleyMotor,
+            f32 "logmotor" $crate::vertices::LogMotor,
+            f32 "skinnedmotor" $crate::vertices::SkinnedMotor,
+            f32 "skinnedmatrix" $crate::vertices::SkinnedMatrix,
+            packed "rotor16" $crate::packed::Rotor16,
+            packed "rotor8" $crate::packed::Rotor8,
+            packed "qrotor1010102" $crate::packed::QRotor1010102,
+            packed "motorhalf" $crate::packed::MotorHalf,
+            f32 "all" $crate::vertices::All,
+        }
+    };
+}
+
+/// The attribute block of a single layout.
+pub fn glsl_block<V: GlslLayout>() -> String {
+    let mut block = format!("#ifdef {}\n#undef DEFAULT_ATTRIBUTES\n", V::DEFINE);
+    for (location, (ty, name)) in V::glsl_attributes().iter().enumerate() {
+        block += &format!("layout (location = {}) in {} {};\n", location, ty, name);
+    }
+    block += V::GLSL_EXTRA;
+    block += "#endif\n";
+    block
+}
+
+/// The attribute blocks of every layout. `All` comes last, its block is
+/// the default when none of the others is selected.
+pub fn glsl_blocks() -> String {
+    macro_rules! blocks {
+        ($( $kind:ident $name:literal $layout:ty, )*) => {
+            [ $( glsl_block::<$layout>(), )* ].join("\n")
+        };
+    }
+    for_each_layout!(blocks)
+}
+
+/// The define that selects a vertex layout, by the name used in suites and
+/// with `--vertex`.
+pub fn attribute_define(vertex: &str) -> &'static str {
+    macro_rules! define {
+        ($( $kind:ident $name:literal $layout:ty, )*) => {
+            match vertex {
+                $( $name => <$layout as GlslLayout>::DEFINE, )*
+                _ => panic!("Unknown vertex layout {}", vertex),
+            }
+        };
+    }
+    for_each_layout!(define)
+}
+
+const BEGIN_GENERATED: &str = "// BEGIN GENERATED ATTRIBUTES (src/layout.rs)\n";
+const END_GENERATED: &str = "// END GENERATED ATTRIBUTES\n";
+
+/// The generated part of a shader source, between its markers, if it has
+/// one.
+pub fn generated_section(source: &str) -> Option<std::ops::Range<usize>> {
+    let begin = source.find(BEGIN_GENERATED)? + BEGIN_GENERATED.len();
+    let end = begin + source[begin..].find(END_GENERATED)?;
+    Some(begin..end)
+}
+
+/// The source with its generated part replaced by the blocks of the
+/// current layouts, see [`glsl_blocks`].
+pub fn with_glsl_blocks(source: &str) -> String {
+    match generated_section(source) {
+        Some(section) => format!(
+            "{}{}{}",
+            &source[..section.start],
+            glsl_blocks(),
+            &source[section.end..]
+        ),
+        None => source.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `GL_MAX_VERTEX_ATTRIBS` is at least 16.
+    const GUARANTEED_LOCATIONS: usize = 16;
+
+    fn assert_fits<V: GlslLayout>() {
+        let locations = V::glsl_attributes().len();
+        assert!(
+            locations <= GUARANTEED_LOCATIONS,
+            "{} takes {} attribute locations",
+            V::DEFINE,
+            locations
+        );
+    }
+
+    #[test]
+    fn every_layout_fits_the_guaranteed_locations() {
+        macro_rules! assert_all_fit {
+            ($( $kind:ident $name:literal $layout:ty, )*) => {
+                $( assert_fits::<$layout>(); )*
+            };
+        }
+        for_each_layout!(assert_all_fit);
+    }
+}
@@ -1,10 +1,28 @@
+pub mod antipodal;
 pub mod app;
 pub mod cameras;
+pub mod glsl;
+pub mod gltf_loader;
+#[cfg(feature = "headless")]
+pub mod headless;
+pub mod hot_reload;
 pub mod imgui_widgets;
+pub mod instancing;
+#[macro_use]
+pub mod layout;
 pub mod lights;
 pub mod material;
+pub mod packed;
 pub mod painters;
+pub mod reference;
+pub mod report;
+pub mod scene_file;
 pub mod shaders;
+pub mod skinning;
+pub mod stats;
+pub mod suite;
+pub mod tangents;
+pub mod techniques;
 pub mod time;
 pub mod utils;
 pub mod vertices;
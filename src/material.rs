@@ -1,8 +1,21 @@
+use crate::gltf_loader;
+use std::rc::Rc;
+
+/// The material of a model, its parameters and the textures it is drawn
+/// with. The app replaces textures that are left out by neutral ones.
+#[derive(Clone, serde::Deserialize)]
+#[serde(default)]
 pub struct Material {
     pub albedo: [f32; 4],
     pub ambient: f32,
     pub specular: f32,
     pub reflectiveness: i32,
+    #[serde(skip)]
+    pub albedo_map: Option<TextureHandle>,
+    #[serde(skip)]
+    pub normal_map: Option<TextureHandle>,
+    #[serde(skip)]
+    pub roughness_map: Option<TextureHandle>,
 }
 
 impl Default for Material {
@@ -12,6 +25,35 @@ impl Default for Material {
             ambient: 0.5,
             specular: 0.5,
             reflectiveness: 32,
+            albedo_map: None,
+            normal_map: None,
+            roughness_map: None,
+        }
+    }
+}
+
+/// A texture of a material, from the scene file or from a glTF file.
+/// Models share their textures.
+#[derive(Clone)]
+pub enum TextureHandle {
+    File(Rc<pgl::texture::Texture>),
+    Gltf(Rc<gltf_loader::Texture>),
+}
+
+impl TextureHandle {
+    pub fn bind_to(&self, slot: u32) {
+        match self {
+            TextureHandle::File(texture) => texture.bind_to(slot).unwrap(),
+            TextureHandle::Gltf(texture) => texture.bind_to(slot),
+        }
+    }
+
+    /// Whether both are handles to the same texture.
+    pub fn same(&self, other: &TextureHandle) -> bool {
+        match (self, other) {
+            (TextureHandle::File(a), TextureHandle::File(b)) => Rc::ptr_eq(a, b),
+            (TextureHandle::Gltf(a), TextureHandle::Gltf(b)) => Rc::ptr_eq(a, b),
+            _ => false,
         }
     }
 }
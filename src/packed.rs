@@ -0,0 +1,430 @@
+//! Quantized variants of the vertex layouts in `vertices`. The compact
+//! encodings only pay off when their components are stored in fewer bits,
+//! so the layouts here keep the position and uv as f32, but store the
+//! tangent frame as normalized integers or half floats.
+//!
+//! `pgl::GlslDType` only knows about f32 attributes, so the packed layouts
+//! describe themselves with [`PackedDType`] and are uploaded with
+//! [`VertexBuffer`], which sets up the (normalized) attribute pointers on
+//! the currently bound vertex array.
+
+use crate::reference::{self, Decode};
+use crate::vertices::{self, All};
+
+/// The type of a single packed vertex attribute, or padding between two
+/// attributes. Normalized integers are read as floats in [-1, 1] by the
+/// shader, so a packed layout can reuse the attribute block of its f32
+/// counterpart whenever the number of components matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackedDType {
+    /// 1 to 4 f32 components.
+    F32(usize),
+    /// 1 to 4 half float components.
+    F16(usize),
+    /// 1 to 4 signed normalized bytes.
+    Snorm8(usize),
+    /// 1 to 4 signed normalized shorts.
+    Snorm16(usize),
+    /// Three signed normalized 10 bit components and one 2 bit component
+    /// in a single u32, `GL_INT_2_10_10_10_REV`.
+    Snorm1010102,
+    /// Bytes that are skipped.
+    Padding(usize),
+}
+
+impl PackedDType {
+    /// Size in bytes.
+    pub const fn size(&self) -> usize {
+        match *self {
+            PackedDType::F32(n) => 4 * n,
+            PackedDType::F16(n) => 2 * n,
+            PackedDType::Snorm8(n) => n,
+            PackedDType::Snorm16(n) => 2 * n,
+            PackedDType::Snorm1010102 => 4,
+            PackedDType::Padding(n) => n,
+        }
+    }
+
+    /// Number of components, type and whether it is normalized, as passed
+    /// to `glVertexAttribPointer`. None for padding.
+    fn gl_format(&self) -> Option<(i32, gl::types::GLenum, bool)> {
+        match *self {
+            PackedDType::F32(n) => Some((n as i32, gl::FLOAT, false)),
+            PackedDType::F16(n) => Some((n as i32, gl::HALF_FLOAT, false)),
+            PackedDType::Snorm8(n) => Some((n as i32, gl::BYTE, true)),
+            PackedDType::Snorm16(n) => Some((n as i32, gl::SHORT, true)),
+            PackedDType::Snorm1010102 => Some((4, gl::INT_2_10_10_10_REV, true)),
+            PackedDType::Padding(_) => None,
+        }
+    }
+}
+
+/// Counterpart of `pgl::vao::HasVertexAttributes` for packed layouts.
+pub trait HasPackedVertexAttributes {
+    fn packed_attributes() -> Vec<PackedDType>;
+}
+
+/// A static vertex buffer holding packed vertices. The attribute pointers
+/// are set on the vertex array that is bound when it is created, the
+/// attribute locations are numbered in order, skipping padding.
+pub struct VertexBuffer {
+    id: gl::types::GLuint,
+}
+
+impl VertexBuffer {
+    pub fn new<V: HasPackedVertexAttributes>(vertices: &[V]) -> Self {
+        let stride = std::mem::size_of::<V>();
+        let attributes = V::packed_attributes();
+        assert_eq!(
+            stride,
+            attributes.iter().map(|a| a.size()).sum::<usize>(),
+            "packed attributes do not match the size of the vertex"
+        );
+
+        let mut id = 0;
+        unsafe {
+            gl::GenBuffers(1, &mut id);
+            gl::BindBuffer(gl::ARRAY_BUFFER, id);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                std::mem::size_of_val(vertices) as gl::types::GLsizeiptr,
+                vertices.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            let mut offset = 0;
+            let mut location = 0;
+            for attribute in attributes.iter() {
+                if let Some((size, ty, normalized)) = attribute.gl_format() {
+                    gl::EnableVertexAttribArray(location);
+                    gl::VertexAttribPointer(
+                        location,
+                        size,
+                        ty,
+                        normalized as gl::types::GLboolean,
+                        stride as gl::types::GLsizei,
+                        offset as *const _,
+                    );
+                    location += 1;
+                }
+                offset += attribute.size();
+            }
+        }
+        Self { id }
+    }
+}
+
+impl Drop for VertexBuffer {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteBuffers(1, &self.id) }
+    }
+}
+
+// QUANTIZATION
+//
+// The unpacking follows the OpenGL 4.2+ conversion rule for signed
+// normalized integers, `max(c / (2^(b-1) - 1), -1)`.
+
+pub fn snorm8(x: f32) -> i8 {
+    (x.clamp(-1., 1.) * 127.).round() as i8
+}
+
+pub fn unsnorm8(c: i8) -> f32 {
+    (c as f32 / 127.).max(-1.)
+}
+
+pub fn snorm16(x: f32) -> i16 {
+    (x.clamp(-1., 1.) * 32767.).round() as i16
+}
+
+pub fn unsnorm16(c: i16) -> f32 {
+    (c as f32 / 32767.).max(-1.)
+}
+
+pub fn f16(x: f32) -> u16 {
+    half::f16::from_f32(x).to_bits()
+}
+
+pub fn unf16(bits: u16) -> f32 {
+    half::f16::from_bits(bits).to_f32()
+}
+
+/// Packs three components in [-1, 1] and a sign in `GL_INT_2_10_10_10_REV`.
+/// The components are truncated instead of rounded, so a unit vector never
+/// becomes longer than one. The sign is stored as -2, which is -1 under
+/// both the old and the new conversion rule.
+pub fn pack_snorm1010102(xyz: [f32; 3], sign: f32) -> u32 {
+    let c = |x: f32| ((x.clamp(-1., 1.) * 511.).trunc() as i32 as u32) & 0x3ff;
+    let w = (if sign < 0. { -2i32 as u32 } else { 1 }) & 0x3;
+    c(xyz[0]) | c(xyz[1]) << 10 | c(xyz[2]) << 20 | w << 30
+}
+
+pub fn unpack_snorm1010102(p: u32) -> ([f32; 3], f32) {
+    // Shift the component to the top and back to sign extend it.
+    let c = |shift: u32| ((((p << (22 - shift)) as i32) >> 22) as f32 / 511.).max(-1.);
+    let w = ((p as i32) >> 30) as f32;
+    ([c(0), c(10), c(20)], w.max(-1.))
+}
+
+// LAYOUTS
+//
+// Declared with `packed_vertex_layout!`, see `layout`.
+
+packed_vertex_layout! {
+    /// Rotor in snorm16.
+    #[derive(Debug)]
+    pub struct Rotor16: "ROTOR16_ATTRIBUTES" {
+        pub position: [f32; 3] => [F32(3) as vec3 aPos],
+        pub uv: [f32; 2] => [F32(2) as vec2 aUV],
+        pub rotor: [i16; 4] => [Snorm16(4) as vec4 aTangentToModelSpaceRotor],
+        pub handedness: i8 => [Snorm8(1) as float aTangentHandedness],
+        _padding: [u8; 3] => [Padding(3)],
+    }
+}
+impl From<&All> for Rotor16 {
+    fn from(v: &All) -> Self {
+        let r = v.rotor;
+        Self {
+            position: v.position,
+            uv: v.uv,
+            rotor: [snorm16(r[0]), snorm16(r[1]), snorm16(r[2]), snorm16(r[3])],
+            handedness: snorm8(v.handedness),
+            _padding: [0; 3],
+        }
+    }
+}
+
+packed_vertex_layout! {
+    /// Rotor in snorm8.
+    #[derive(Debug)]
+    pub struct Rotor8: "ROTOR8_ATTRIBUTES" {
+        pub position: [f32; 3] => [F32(3) as vec3 aPos],
+        pub uv: [f32; 2] => [F32(2) as vec2 aUV],
+        pub rotor: [i8; 4] => [Snorm8(4) as vec4 aTangentToModelSpaceRotor],
+        pub handedness: i8 => [Snorm8(1) as float aTangentHandedness],
+        _padding: [u8; 3] => [Padding(3)],
+    }
+}
+impl From<&All> for Rotor8 {
+    fn from(v: &All) -> Self {
+        let r = v.rotor;
+        Self {
+            position: v.position,
+            uv: v.uv,
+            rotor: [snorm8(r[0]), snorm8(r[1]), snorm8(r[2]), snorm8(r[3])],
+            handedness: snorm8(v.handedness),
+            _padding: [0; 3],
+        }
+    }
+}
+
+packed_vertex_layout! {
+    /// QTangent bivector in 10-10-10 with the handedness in the 2 bit
+    /// component.
+    #[derive(Debug)]
+    pub struct QRotor1010102: "QROTOR_PACKED_ATTRIBUTES" {
+        pub position: [f32; 3] => [F32(3) as vec3 aPos],
+        pub uv: [f32; 2] => [F32(2) as vec2 aUV],
+        pub qtang_handedness: u32 => [Snorm1010102 as vec4 aTangentToModelSpaceQTangAndHandedness],
+    }
+    "#define aTangentToModelSpaceQTang aTangentToModelSpaceQTangAndHandedness.xyz\n\
+     #define aTangentHandedness aTangentToModelSpaceQTangAndHandedness.w\n"
+}
+impl From<&All> for QRotor1010102 {
+    fn from(v: &All) -> Self {
+        Self {
+            position: v.position,
+            uv: v.uv,
+            qtang_handedness: pack_snorm1010102(v.qtang, v.handedness),
+        }
+    }
+}
+
+packed_vertex_layout! {
+    /// Motor stored as a snorm16 rotor and a half float translation, the
+    /// vanishing part is rebuilt in the shader. The handedness is in the w
+    /// component of the translation.
+    #[derive(Debug)]
+    pub struct MotorHalf: "MOTOR_PACKED_ATTRIBUTES" {
+        pub position: [f32; 3] => [F32(3) as vec3 aPos],
+        pub uv: [f32; 2] => [F32(2) as vec2 aUV],
+        pub rotor: [i16; 4] => [Snorm16(4) as vec4 aTangentToModelSpaceRotor],
+        pub translation_handedness: [u16; 4] =>
+            [F16(4) as vec4 aTangentToModelSpaceTranslationAndHandedness],
+    }
+    "#define aTangentHandedness aTangentToModelSpaceTranslationAndHandedness.w\n\
+     #define PACKED_MOTOR\n"
+}
+impl From<&All> for MotorHalf {
+    fn from(v: &All) -> Self {
+        let r = v.motor[0];
+        let t = reference::apply_motor_to_origin(v.motor);
+        Self {
+            position: v.position,
+            uv: v.uv,
+            rotor: [snorm16(r[0]), snorm16(r[1]), snorm16(r[2]), snorm16(r[3])],
+            translation_handedness: [f16(t[0]), f16(t[1]), f16(t[2]), f16(v.handedness)],
+        }
+    }
+}
+
+impl_from_pos_uv_norm_tang!(Rotor16);
+impl_from_pos_uv_norm_tang!(Rotor8);
+impl_from_pos_uv_norm_tang!(QRotor1010102);
+impl_from_pos_uv_norm_tang!(MotorHalf);
+
+// DECODING
+//
+// The packed layouts are unpacked the way the vertex fetch does it and
+// then decoded like their f32 counterparts, so the only difference in the
+// result is the precision that is lost.
+
+impl Decode for Rotor16 {
+    fn fragment_normal(v: [&Self; 3], b: [f32; 3], n: [f32; 3]) -> [f32; 3] {
+        let unpack = |v: &Self| vertices::Rotor {
+            position: v.position,
+            uv: v.uv,
+            rotor: [
+                unsnorm16(v.rotor[0]),
+                unsnorm16(v.rotor[1]),
+                unsnorm16(v.rotor[2]),
+                unsnorm16(v.rotor[3]),
+            ],
+            handedness: unsnorm8(v.handedness),
+        };
+        let (a, b_, c) = (unpack(v[0]), unpack(v[1]), unpack(v[2]));
+        vertices::Rotor::fragment_normal([&a, &b_, &c], b, n)
+    }
+}
+
+impl Decode for Rotor8 {
+    fn fragment_normal(v: [&Self; 3], b: [f32; 3], n: [f32; 3]) -> [f32; 3] {
+        let unpack = |v: &Self| vertices::Rotor {
+            position: v.position,
+            uv: v.uv,
+            rotor: [
+                unsnorm8(v.rotor[0]),
+                unsnorm8(v.rotor[1]),
+                unsnorm8(v.rotor[2]),
+                unsnorm8(v.rotor[3]),
+            ],
+            handedness: unsnorm8(v.handedness),
+        };
+        let (a, b_, c) = (unpack(v[0]), unpack(v[1]), unpack(v[2]));
+        vertices::Rotor::fragment_normal([&a, &b_, &c], b, n)
+    }
+}
+
+impl Decode for QRotor1010102 {
+    fn fragment_normal(v: [&Self; 3], b: [f32; 3], n: [f32; 3]) -> [f32; 3] {
+        let unpack = |v: &Self| {
+            let (qtang, handedness) = unpack_snorm1010102(v.qtang_handedness);
+            vertices::QRotor {
+                position: v.position,
+                uv: v.uv,
+                outer_rotor: qtang,
+                handedness,
+            }
+        };
+        let (a, b_, c) = (unpack(v[0]), unpack(v[1]), unpack(v[2]));
+        vertices::QRotor::fragment_normal([&a, &b_, &c], b, n)
+    }
+}
+
+impl Decode for MotorHalf {
+    fn fragment_normal(v: [&Self; 3], b: [f32; 3], n: [f32; 3]) -> [f32; 3] {
+        let unpack = |v: &Self| {
+            let rotor = [
+                unsnorm16(v.rotor[0]),
+                unsnorm16(v.rotor[1]),
+                unsnorm16(v.rotor[2]),
+                unsnorm16(v.rotor[3]),
+            ];
+            let t = v.translation_handedness;
+            vertices::Motor {
+                position: v.position,
+                uv: v.uv,
+                motor: reference::motor_from_rotor_translation(
+                    rotor,
+                    [unf16(t[0]), unf16(t[1]), unf16(t[2])],
+                ),
+                handedness: unf16(t[3]),
+            }
+        };
+        let (a, b_, c) = (unpack(v[0]), unpack(v[1]), unpack(v[2]));
+        vertices::Motor::fragment_normal([&a, &b_, &c], b, n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Evenly spaced values from -1 to 1, both included.
+    fn unit_range() -> impl Iterator<Item = f32> {
+        const STEPS: i32 = 20_000;
+        (-STEPS..=STEPS).map(|i| i as f32 / STEPS as f32)
+    }
+
+    #[test]
+    fn snorm8_rounds_to_the_nearest_step() {
+        let bound = 0.5 / 127. + f32::EPSILON;
+        for x in unit_range() {
+            assert!((unsnorm8(snorm8(x)) - x).abs() <= bound, "{}", x);
+        }
+    }
+
+    #[test]
+    fn snorm16_rounds_to_the_nearest_step() {
+        let bound = 0.5 / 32767. + f32::EPSILON;
+        for x in unit_range() {
+            assert!((unsnorm16(snorm16(x)) - x).abs() <= bound, "{}", x);
+        }
+    }
+
+    #[test]
+    fn snorm_clamps_to_unit_range() {
+        assert_eq!(unsnorm8(snorm8(2.)), 1.);
+        assert_eq!(unsnorm8(snorm8(-2.)), -1.);
+        assert_eq!(unsnorm8(i8::MIN), -1.);
+        assert_eq!(unsnorm16(snorm16(2.)), 1.);
+        assert_eq!(unsnorm16(snorm16(-2.)), -1.);
+        assert_eq!(unsnorm16(i16::MIN), -1.);
+        let (xyz, _) = unpack_snorm1010102(pack_snorm1010102([2., -2., 0.], 1.));
+        assert_eq!(xyz, [1., -1., 0.]);
+    }
+
+    #[test]
+    fn snorm1010102_truncates_within_a_step() {
+        for x in unit_range() {
+            for sign in [1., -1.].iter() {
+                let (xyz, w) = unpack_snorm1010102(pack_snorm1010102([x, -x, 0.5 * x], *sign));
+                assert_eq!(w, *sign);
+                for (unpacked, original) in xyz.iter().zip([x, -x, 0.5 * x].iter()) {
+                    assert!((unpacked - original).abs() < 1. / 511., "{}", original);
+                    assert!(unpacked.abs() <= original.abs(), "{}", original);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn snorm1010102_keeps_unit_vectors_within_the_unit_sphere() {
+        for x in unit_range() {
+            let v = [x, (1. - x * x).sqrt(), 0.];
+            let (xyz, _) = unpack_snorm1010102(pack_snorm1010102(v, 1.));
+            assert!(xyz.iter().map(|c| c * c).sum::<f32>() <= 1., "{:?}", v);
+        }
+    }
+
+    #[test]
+    fn f16_has_eleven_bits_of_precision() {
+        // The smallest normal half float is 2^-14.
+        let bound = 2f32.powi(-11);
+        for x in unit_range().filter(|x| x.abs() >= 2f32.powi(-14)) {
+            assert!((unf16(f16(x)) - x).abs() <= bound * x.abs(), "{}", x);
+        }
+        for x in [-1., 0., 1.].iter() {
+            assert_eq!(unf16(f16(*x)), *x);
+        }
+    }
+}
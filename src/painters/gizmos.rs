@@ -1,3 +1,4 @@
+use crate::shaders;
 use crate::vertices::PosNorm;
 use pgl::vao::VertexArray;
 
@@ -28,11 +29,7 @@ impl Gizmos {
         VertexArray::unbind();
         Self {
             vao,
-            shader: pgl::shader::ShaderProgram::from_path(
-                "shaders/gizmos.glsl",
-                Default::default(),
-            )
-            .unwrap(),
+            shader: shaders::load("shaders/gizmos.glsl", Default::default()).unwrap(),
             n_faces: i.len(),
         }
     }
@@ -0,0 +1,762 @@
+//! CPU reference for the decoding that the normal mapping shaders do.
+//!
+//! Every function mirrors its counterpart in `shaders/headers/ppga.glsl`
+//! operation for operation in f32, including the swizzles, so that the
+//! result is the same as on the GPU up to fused multiply-adds. On top of
+//! that, [`Decode`] reproduces the per fragment normal of each shader from
+//! the barycentric interpolation of the vertex attributes, which makes it
+//! possible to compare the encodings against the matrix path without a GPU.
+
+use crate::{packed, vertices};
+
+// SMALL GLSL LOOKALIKES
+
+fn dot4(a: [f32; 4], b: [f32; 4]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2] + a[3] * b[3]
+}
+
+fn dot3(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn mul4(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+    [a[0] * b[0], a[1] * b[1], a[2] * b[2], a[3] * b[3]]
+}
+
+fn scale4(s: f32, a: [f32; 4]) -> [f32; 4] {
+    [s * a[0], s * a[1], s * a[2], s * a[3]]
+}
+
+fn scale3(s: f32, a: [f32; 3]) -> [f32; 3] {
+    [s * a[0], s * a[1], s * a[2]]
+}
+
+fn add3(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub3(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn mul3(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] * b[0], a[1] * b[1], a[2] * b[2]]
+}
+
+/// GLSL style swizzle, `swizzle(v, [3, 1, 3, 0])` is `v.wywx`.
+fn swizzle<const N: usize, const M: usize>(v: [f32; N], idx: [usize; M]) -> [f32; M] {
+    let mut res = [0.; M];
+    for (r, i) in res.iter_mut().zip(idx.iter()) {
+        *r = v[*i];
+    }
+    res
+}
+
+pub fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - b[1] * a[2],
+        a[2] * b[0] - b[2] * a[0],
+        a[0] * b[1] - b[0] * a[1],
+    ]
+}
+
+pub fn normalize(v: [f32; 3]) -> [f32; 3] {
+    scale3(1. / dot3(v, v).sqrt(), v)
+}
+
+const X: usize = 0;
+const Y: usize = 1;
+const Z: usize = 2;
+const W: usize = 3;
+
+// PPGA.GLSL
+
+pub fn apply_rotor_to_direction(r: [f32; 4], d: [f32; 3]) -> [f32; 3] {
+    let mask1 = [1., 1., -1., -1.];
+    let mask2 = [1., 1., 1., -1.];
+
+    let res = [
+        dot4(
+            mul4(
+                mul4(swizzle(d, [Y, Y, Z, Z]), swizzle(r, [W, Y, W, X])),
+                swizzle(r, [X, Z, Y, Z]),
+            ),
+            mask2,
+        ),
+        dot4(
+            mul4(
+                mul4(swizzle(d, [X, Z, Z, X]), swizzle(r, [Y, W, X, W])),
+                swizzle(r, [Z, Z, Y, X]),
+            ),
+            mask2,
+        ),
+        dot4(
+            mul4(
+                mul4(swizzle(d, [X, X, Y, Y]), swizzle(r, [W, X, W, X])),
+                swizzle(r, [Y, Z, Z, Y]),
+            ),
+            mask2,
+        ),
+    ];
+    let res = scale3(2.0, res);
+    let rsq = mul4(r, r);
+    add3(
+        res,
+        [
+            dot4(mask1, scale4(d[X], rsq)),
+            dot4(mask1, scale4(d[Y], swizzle(rsq, [X, Z, Y, W]))),
+            dot4(mask1, scale4(d[Z], swizzle(rsq, [X, W, Y, Z]))),
+        ],
+    )
+}
+
+pub fn mirror_tangent_space(d: [f32; 3], handedness: f32) -> [f32; 3] {
+    [d[X], if handedness < 0.0 { -d[Y] } else { d[Y] }, d[Z]]
+}
+
+pub fn apply_motor_to_direction(m: [[f32; 4]; 2], dir: [f32; 3]) -> [f32; 3] {
+    apply_rotor_to_direction(m[0], dir)
+}
+
+pub fn apply_motor_to_origin(m: [[f32; 4]; 2]) -> [f32; 3] {
+    let (p1, p2) = (m[0], m[1]);
+    let mut res = scale3(p2[X], swizzle(p1, [Y, Z, W]));
+    res = add3(res, scale3(p1[X], swizzle(p2, [Y, Z, W])));
+    res = sub3(res, mul3(swizzle(p1, [Z, W, Y]), swizzle(p2, [W, Y, Z])));
+    res = add3(res, mul3(swizzle(p1, [W, Y, Z]), swizzle(p2, [Z, W, Y])));
+    scale3(2.0, res)
+}
+
+pub fn motor_from_rotor_translation(r: [f32; 4], t: [f32; 3]) -> [[f32; 4]; 2] {
+    let a = swizzle(r, [Y, Z, W]);
+    let tau = scale3(0.5, t);
+    let v = add3(scale3(r[X], tau), cross(a, tau));
+    [r, [dot3(tau, a), v[X], v[Y], v[Z]]]
+}
+
+/// Below this squared angle `exp_motor` and `ln_motor` use Taylor
+/// expansions instead of dividing by the angle.
+const SMALL_ANGLE_SQUARED: f32 = 1e-3;
+
+/// `ppga_exp`, the exponential of the bivector with euclidean part `eucl`
+/// (e23, e31, e12) and vanishing part `vanish` (e01, e02, e03).
+pub fn exp_motor(eucl: [f32; 3], vanish: [f32; 3]) -> [[f32; 4]; 2] {
+    let uu = dot3(eucl, eucl);
+    let u = uu.sqrt();
+    let ab = dot3(eucl, vanish);
+    let (sinc, f) = if uu < SMALL_ANGLE_SQUARED {
+        (1.0 - uu / 6.0, -1.0 / 3.0 + uu / 30.0)
+    } else {
+        let sinc = u.sin() / u;
+        (sinc, (u.cos() - sinc) / uu)
+    };
+    let p1 = scale3(sinc, eucl);
+    let p2 = add3(scale3(sinc, vanish), scale3(ab * f, eucl));
+    [
+        [u.cos(), p1[X], p1[Y], p1[Z]],
+        [ab * sinc, p2[X], p2[Y], p2[Z]],
+    ]
+}
+
+/// Inverse of [`exp_motor`], returns the euclidean and vanishing part of
+/// the bivector. Not in the shaders, the logarithm is taken on the CPU
+/// when the vertices are built. The motor is normalized first and its sign
+/// is picked such that the scalar is positive, so the angle is at most
+/// half a turn and the result is the same for `m` and `-m`.
+pub fn ln_motor(m: [[f32; 4]; 2]) -> [[f32; 3]; 2] {
+    let norm = dot4(m[0], m[0]).sqrt();
+    let sign = if m[0][X] < 0. { -1. } else { 1. };
+    let p1 = scale4(sign / norm, m[0]);
+    let p2 = scale4(sign / norm, m[1]);
+
+    let s = p1[X];
+    let a_ = swizzle(p1, [Y, Z, W]);
+    let v = swizzle(p2, [Y, Z, W]);
+    let xx = dot3(a_, a_);
+    let x = xx.sqrt();
+    let u = x.atan2(s);
+    let uu = u * u;
+
+    // Same sinc and f as in the exponential, in terms of the angle u.
+    let (sinc, f) = if uu < SMALL_ANGLE_SQUARED {
+        (1.0 - uu / 6.0, -1.0 / 3.0 + uu / 30.0)
+    } else {
+        let sinc = u.sin() / u;
+        (sinc, (u.cos() - sinc) / uu)
+    };
+    let eucl = scale3(1. / sinc, a_);
+    let ab = p2[X] / sinc;
+    let vanish = scale3(1. / sinc, sub3(v, scale3(ab * f, eucl)));
+    [eucl, vanish]
+}
+
+pub fn cayley_exp_motor(eucl: [f32; 3], vanish: [f32; 3]) -> [[f32; 4]; 2] {
+    [
+        [0.5 - 0.5 * dot3(eucl, eucl), -eucl[X], -eucl[Y], -eucl[Z]],
+        [dot3(eucl, vanish), -vanish[X], -vanish[Y], -vanish[Z]],
+    ]
+}
+
+pub fn cayley_exp_rotor(eucl: [f32; 3]) -> [f32; 4] {
+    [0.5 - 0.5 * dot3(eucl, eucl), -eucl[X], -eucl[Y], -eucl[Z]]
+}
+
+pub fn outer_exp_motor(eucl: [f32; 3], vanish: [f32; 3]) -> [[f32; 4]; 2] {
+    let p1 = [1.0, eucl[X], eucl[Y], eucl[Z]];
+    let normalizer = 1. / dot4(p1, p1).sqrt();
+    [
+        scale4(normalizer, p1),
+        scale4(
+            normalizer,
+            [dot3(vanish, eucl), vanish[X], vanish[Y], vanish[Z]],
+        ),
+    ]
+}
+
+pub fn outer_exp_rotor(eucl: [f32; 3]) -> [f32; 4] {
+    let p1 = [1.0, eucl[X], eucl[Y], eucl[Z]];
+    let normalizer = 1. / dot4(p1, p1).sqrt();
+    scale4(normalizer, p1)
+}
+
+pub fn qtangent_exp(eucl: [f32; 3]) -> [f32; 4] {
+    let w = (1. - dot3(eucl, eucl)).sqrt();
+    [w, eucl[X], eucl[Y], eucl[Z]]
+}
+
+// INTERPOLATION
+
+/// Linear interpolation of a vertex attribute over a triangle, in the
+/// order the rasterizer would sum it.
+pub fn interpolate<const N: usize>(values: [[f32; N]; 3], barycentric: [f32; 3]) -> [f32; N] {
+    let mut res = [0.; N];
+    for (i, r) in res.iter_mut().enumerate() {
+        *r = barycentric[0] * values[0][i]
+            + barycentric[1] * values[1][i]
+            + barycentric[2] * values[2][i];
+    }
+    res
+}
+
+fn interpolate_scalar(values: [f32; 3], barycentric: [f32; 3]) -> f32 {
+    interpolate([[values[0]], [values[1]], [values[2]]], barycentric)[0]
+}
+
+fn interpolate_motor(values: [[[f32; 4]; 2]; 3], barycentric: [f32; 3]) -> [[f32; 4]; 2] {
+    [
+        interpolate([values[0][0], values[1][0], values[2][0]], barycentric),
+        interpolate([values[0][1], values[1][1], values[2][1]], barycentric),
+    ]
+}
+
+fn interpolate_bivectors(values: [[[f32; 3]; 2]; 3], barycentric: [f32; 3]) -> [[f32; 3]; 2] {
+    [
+        interpolate([values[0][0], values[1][0], values[2][0]], barycentric),
+        interpolate([values[0][1], values[1][1], values[2][1]], barycentric),
+    ]
+}
+
+/// `normalize(mat3(tangent, bitangent, normal) * n)` like the matrix shaders.
+fn apply_frame(frame: [[f32; 3]; 3], n: [f32; 3]) -> [f32; 3] {
+    let res = add3(
+        add3(scale3(n[X], frame[0]), scale3(n[Y], frame[1])),
+        scale3(n[Z], frame[2]),
+    );
+    normalize(res)
+}
+
+// PER SHADER DECODING
+
+/// The normal mapping shaders, each decoding the tangent frame differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Matrix,
+    NormalTangent,
+    Rotor,
+    Motor,
+    OuterRotor,
+    OuterMotor,
+    CayleyRotor,
+    CayleyMotor,
+    QRotor,
+    LogMotor,
+    Rotor16,
+    Rotor8,
+    QRotor1010102,
+    MotorHalf,
+}
+
+impl Encoding {
+    pub const ALL: [Encoding; 14] = [
+        Encoding::Matrix,
+        Encoding::NormalTangent,
+        Encoding::Rotor,
+        Encoding::Motor,
+        Encoding::OuterRotor,
+        Encoding::OuterMotor,
+        Encoding::CayleyRotor,
+        Encoding::CayleyMotor,
+        Encoding::QRotor,
+        Encoding::LogMotor,
+        Encoding::Rotor16,
+        Encoding::Rotor8,
+        Encoding::QRotor1010102,
+        Encoding::MotorHalf,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Matrix => "matrix",
+            Encoding::NormalTangent => "normtang",
+            Encoding::Rotor => "rotor",
+            Encoding::Motor => "motor",
+            Encoding::OuterRotor => "outerrotor",
+            Encoding::OuterMotor => "outermotor",
+            Encoding::CayleyRotor => "cayleyrotor",
+            Encoding::CayleyMotor => "cayleymotor",
+            Encoding::QRotor => "qrotor",
+            Encoding::LogMotor => "logmotor",
+            Encoding::Rotor16 => "rotor16",
+            Encoding::Rotor8 => "rotor8",
+            Encoding::QRotor1010102 => "qrotor1010102",
+            Encoding::MotorHalf => "motorhalf",
+        }
+    }
+}
+
+/// Reproduces what the fragment shader of an encoding computes for the
+/// world space normal, given the three vertices of the triangle, the
+/// barycentric coordinates of the fragment and the tangent space normal
+/// read from the normal map.
+pub trait Decode {
+    fn fragment_normal(vertices: [&Self; 3], barycentric: [f32; 3], normal: [f32; 3]) -> [f32; 3];
+}
+
+impl Decode for vertices::Matrix {
+    fn fragment_normal(v: [&Self; 3], b: [f32; 3], n: [f32; 3]) -> [f32; 3] {
+        let frame = [
+            interpolate([v[0].tangent, v[1].tangent, v[2].tangent], b),
+            interpolate([v[0].bitangent, v[1].bitangent, v[2].bitangent], b),
+            interpolate([v[0].normal, v[1].normal, v[2].normal], b),
+        ];
+        apply_frame(frame, n)
+    }
+}
+
+impl Decode for vertices::PosUVNormTang {
+    fn fragment_normal(v: [&Self; 3], b: [f32; 3], n: [f32; 3]) -> [f32; 3] {
+        let bitangent = |v: &Self| scale3(v.handedness, cross(v.normal, v.tangent));
+        let frame = [
+            interpolate([v[0].tangent, v[1].tangent, v[2].tangent], b),
+            interpolate([bitangent(v[0]), bitangent(v[1]), bitangent(v[2])], b),
+            interpolate([v[0].normal, v[1].normal, v[2].normal], b),
+        ];
+        apply_frame(frame, n)
+    }
+}
+
+impl Decode for vertices::Rotor {
+    fn fragment_normal(v: [&Self; 3], b: [f32; 3], n: [f32; 3]) -> [f32; 3] {
+        let rotor = interpolate([v[0].rotor, v[1].rotor, v[2].rotor], b);
+        let h = interpolate_scalar([v[0].handedness, v[1].handedness, v[2].handedness], b);
+        normalize(apply_rotor_to_direction(rotor, mirror_tangent_space(n, h)))
+    }
+}
+
+impl Decode for vertices::Motor {
+    fn fragment_normal(v: [&Self; 3], b: [f32; 3], n: [f32; 3]) -> [f32; 3] {
+        let motor = interpolate_motor([v[0].motor, v[1].motor, v[2].motor], b);
+        let h = interpolate_scalar([v[0].handedness, v[1].handedness, v[2].handedness], b);
+        normalize(apply_motor_to_direction(motor, mirror_tangent_space(n, h)))
+    }
+}
+
+impl Decode for vertices::OuterRotor {
+    fn fragment_normal(v: [&Self; 3], b: [f32; 3], n: [f32; 3]) -> [f32; 3] {
+        let param = interpolate([v[0].outer_rotor, v[1].outer_rotor, v[2].outer_rotor], b);
+        let h = interpolate_scalar([v[0].handedness, v[1].handedness, v[2].handedness], b);
+        let rotor = outer_exp_rotor(param);
+        normalize(apply_rotor_to_direction(rotor, mirror_tangent_space(n, h)))
+    }
+}
+
+impl Decode for vertices::OuterMotor {
+    fn fragment_normal(v: [&Self; 3], b: [f32; 3], n: [f32; 3]) -> [f32; 3] {
+        let [e, v_] =
+            interpolate_bivectors([v[0].outer_motor, v[1].outer_motor, v[2].outer_motor], b);
+        let h = interpolate_scalar([v[0].handedness, v[1].handedness, v[2].handedness], b);
+        let motor = outer_exp_motor(e, v_);
+        normalize(apply_motor_to_direction(motor, mirror_tangent_space(n, h)))
+    }
+}
+
+impl Decode for vertices::CayleyRotor {
+    fn fragment_normal(v: [&Self; 3], b: [f32; 3], n: [f32; 3]) -> [f32; 3] {
+        let param = interpolate([v[0].cayley_rotor, v[1].cayley_rotor, v[2].cayley_rotor], b);
+        let h = interpolate_scalar([v[0].handedness, v[1].handedness, v[2].handedness], b);
+        let rotor = cayley_exp_rotor(param);
+        normalize(apply_rotor_to_direction(rotor, mirror_tangent_space(n, h)))
+    }
+}
+
+impl Decode for vertices::CayleyMotor {
+    fn fragment_normal(v: [&Self; 3], b: [f32; 3], n: [f32; 3]) -> [f32; 3] {
+        let [e, v_] =
+            interpolate_bivectors([v[0].cayley_motor, v[1].cayley_motor, v[2].cayley_motor], b);
+        let h = interpolate_scalar([v[0].handedness, v[1].handedness, v[2].handedness], b);
+        let motor = cayley_exp_motor(e, v_);
+        normalize(apply_motor_to_direction(motor, mirror_tangent_space(n, h)))
+    }
+}
+
+impl Decode for vertices::QRotor {
+    fn fragment_normal(v: [&Self; 3], b: [f32; 3], n: [f32; 3]) -> [f32; 3] {
+        let param = interpolate([v[0].outer_rotor, v[1].outer_rotor, v[2].outer_rotor], b);
+        let h = interpolate_scalar([v[0].handedness, v[1].handedness, v[2].handedness], b);
+        let rotor = qtangent_exp(param);
+        normalize(apply_rotor_to_direction(rotor, mirror_tangent_space(n, h)))
+    }
+}
+
+impl Decode for vertices::LogMotor {
+    fn fragment_normal(v: [&Self; 3], b: [f32; 3], n: [f32; 3]) -> [f32; 3] {
+        let [e, v_] = interpolate_bivectors([v[0].log_motor, v[1].log_motor, v[2].log_motor], b);
+        let h = interpolate_scalar([v[0].handedness, v[1].handedness, v[2].handedness], b);
+        let motor = exp_motor(e, v_);
+        normalize(apply_motor_to_direction(motor, mirror_tangent_space(n, h)))
+    }
+}
+
+/// Same as [`Decode::fragment_normal`] for the vertex that holds every
+/// encoding at once, the encoding to decode is picked with `encoding`.
+pub fn fragment_normal(
+    encoding: Encoding,
+    v: [&vertices::All; 3],
+    b: [f32; 3],
+    n: [f32; 3],
+) -> [f32; 3] {
+    let h = interpolate_scalar([v[0].handedness, v[1].handedness, v[2].handedness], b);
+    let mirrored = mirror_tangent_space(n, h);
+    match encoding {
+        Encoding::Matrix => {
+            let frame = [
+                interpolate([v[0].tangent, v[1].tangent, v[2].tangent], b),
+                interpolate([v[0].bitangent, v[1].bitangent, v[2].bitangent], b),
+                interpolate([v[0].normal, v[1].normal, v[2].normal], b),
+            ];
+            apply_frame(frame, n)
+        }
+        Encoding::NormalTangent => {
+            let bitangent = |v: &vertices::All| scale3(v.handedness, cross(v.normal, v.tangent));
+            let frame = [
+                interpolate([v[0].tangent, v[1].tangent, v[2].tangent], b),
+                interpolate([bitangent(v[0]), bitangent(v[1]), bitangent(v[2])], b),
+                interpolate([v[0].normal, v[1].normal, v[2].normal], b),
+            ];
+            apply_frame(frame, n)
+        }
+        Encoding::Rotor => {
+            let rotor = interpolate([v[0].rotor, v[1].rotor, v[2].rotor], b);
+            normalize(apply_rotor_to_direction(rotor, mirrored))
+        }
+        Encoding::Motor => {
+            let motor = interpolate_motor([v[0].motor, v[1].motor, v[2].motor], b);
+            normalize(apply_motor_to_direction(motor, mirrored))
+        }
+        Encoding::OuterRotor => {
+            let param = interpolate(
+                [
+                    v[0].outer_log_motor[0],
+                    v[1].outer_log_motor[0],
+                    v[2].outer_log_motor[0],
+                ],
+                b,
+            );
+            normalize(apply_rotor_to_direction(outer_exp_rotor(param), mirrored))
+        }
+        Encoding::OuterMotor => {
+            let [e, v_] = interpolate_bivectors(
+                [
+                    v[0].outer_log_motor,
+                    v[1].outer_log_motor,
+                    v[2].outer_log_motor,
+                ],
+                b,
+            );
+            normalize(apply_motor_to_direction(outer_exp_motor(e, v_), mirrored))
+        }
+        Encoding::CayleyRotor => {
+            let param = interpolate(
+                [
+                    v[0].cayley_motor[0],
+                    v[1].cayley_motor[0],
+                    v[2].cayley_motor[0],
+                ],
+                b,
+            );
+            normalize(apply_rotor_to_direction(cayley_exp_rotor(param), mirrored))
+        }
+        Encoding::CayleyMotor => {
+            let [e, v_] =
+                interpolate_bivectors([v[0].cayley_motor, v[1].cayley_motor, v[2].cayley_motor], b);
+            normalize(apply_motor_to_direction(cayley_exp_motor(e, v_), mirrored))
+        }
+        Encoding::QRotor => {
+            let param = interpolate([v[0].qtang, v[1].qtang, v[2].qtang], b);
+            normalize(apply_rotor_to_direction(qtangent_exp(param), mirrored))
+        }
+        Encoding::LogMotor => {
+            let [e, v_] =
+                interpolate_bivectors([v[0].log_motor, v[1].log_motor, v[2].log_motor], b);
+            normalize(apply_motor_to_direction(exp_motor(e, v_), mirrored))
+        }
+        Encoding::Rotor16 => decode_packed::<packed::Rotor16>(v, b, n),
+        Encoding::Rotor8 => decode_packed::<packed::Rotor8>(v, b, n),
+        Encoding::QRotor1010102 => decode_packed::<packed::QRotor1010102>(v, b, n),
+        Encoding::MotorHalf => decode_packed::<packed::MotorHalf>(v, b, n),
+    }
+}
+
+/// Packs the vertices first, so the precision lost to the packing shows.
+fn decode_packed<P>(v: [&vertices::All; 3], b: [f32; 3], n: [f32; 3]) -> [f32; 3]
+where
+    P: Decode + for<'a> From<&'a vertices::All>,
+{
+    let p = [P::from(v[0]), P::from(v[1]), P::from(v[2])];
+    P::fragment_normal([&p[0], &p[1], &p[2]], b, n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use std::f32::consts::PI;
+
+    /// Largest component difference after `exp(ln(m))` that is accepted.
+    const ROUNDTRIP_TOLERANCE: f32 = 1e-5;
+
+    /// Rotates by `angle` around `axis` and then translates by `t`.
+    fn motor(angle: f32, axis: [f32; 3], t: [f32; 3]) -> [[f32; 4]; 2] {
+        let axis = normalize(axis);
+        let s = -(0.5 * angle).sin();
+        let rotor = [(0.5 * angle).cos(), s * axis[X], s * axis[Y], s * axis[Z]];
+        motor_from_rotor_translation(rotor, t)
+    }
+
+    /// Largest component difference, `m` and `-m` are the same motor.
+    fn motor_distance(a: [[f32; 4]; 2], b: [[f32; 4]; 2]) -> f32 {
+        let mut same = 0f32;
+        let mut opposite = 0f32;
+        for (a, b) in a.iter().flatten().zip(b.iter().flatten()) {
+            same = same.max((a - b).abs());
+            opposite = opposite.max((a + b).abs());
+        }
+        same.min(opposite)
+    }
+
+    /// The largest roundtrip error of random motors with an angle between
+    /// `min_angle` and `max_angle`.
+    fn max_roundtrip_error(min_angle: f32, max_angle: f32) -> f32 {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let mut max_error = 0f32;
+        for _ in 0..10_000 {
+            let angle = rng.gen_range(min_angle..=max_angle);
+            let axis = [
+                rng.gen_range(-1f32..1.),
+                rng.gen_range(-1f32..1.),
+                rng.gen_range(0.1f32..1.),
+            ];
+            let t = [
+                rng.gen_range(-10f32..10.),
+                rng.gen_range(-10f32..10.),
+                rng.gen_range(-10f32..10.),
+            ];
+            let m = motor(angle, axis, t);
+            let [eucl, vanish] = ln_motor(m);
+            max_error = max_error.max(motor_distance(m, exp_motor(eucl, vanish)));
+        }
+        max_error
+    }
+
+    #[test]
+    fn exp_inverts_ln() {
+        assert!(max_roundtrip_error(0., PI) <= ROUNDTRIP_TOLERANCE);
+    }
+
+    #[test]
+    fn exp_inverts_ln_near_identity() {
+        assert!(max_roundtrip_error(0., 1e-3) <= ROUNDTRIP_TOLERANCE);
+        let identity = motor(0., [0., 0., 1.], [1., 2., 3.]);
+        let [eucl, vanish] = ln_motor(identity);
+        assert!(motor_distance(identity, exp_motor(eucl, vanish)) <= ROUNDTRIP_TOLERANCE);
+    }
+
+    /// Around the angle where the Taylor expansions take over, the motor
+    /// has half the angle of the rotation.
+    #[test]
+    fn exp_inverts_ln_around_the_small_angle_switch() {
+        let switch = 2. * SMALL_ANGLE_SQUARED.sqrt();
+        assert!(max_roundtrip_error(0.9 * switch, 1.1 * switch) <= ROUNDTRIP_TOLERANCE);
+    }
+
+    #[test]
+    fn exp_inverts_ln_near_half_turn() {
+        assert!(max_roundtrip_error(PI - 1e-3, PI) <= ROUNDTRIP_TOLERANCE);
+    }
+
+    #[test]
+    fn ln_is_the_same_for_both_signs() {
+        let m = motor(2., [1., 2., 3.], [4., 5., 6.]);
+        let minus = [scale4(-1., m[0]), scale4(-1., m[1])];
+        assert_eq!(ln_motor(m), ln_motor(minus));
+    }
+
+    /// Largest component difference of two normals.
+    fn normal_distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+        let d = sub3(a, b);
+        d[X].abs().max(d[Y].abs()).max(d[Z].abs())
+    }
+
+    /// Tangent frames rotated away from the standard basis by up to 2.5
+    /// radians, with both handedness. Far from half a turn, where the outer
+    /// logarithm breaks down.
+    fn frames() -> Vec<vertices::PosUVNormTang> {
+        let rotations = [
+            (0., [0., 0., 1.]),
+            (0.5, [1., 0., 0.]),
+            (1.2, [0., 1., 0.]),
+            (2.5, [0., 0., 1.]),
+            (1.9, [1., -2., 0.5]),
+            (2.2, [-0.3, 0.4, 1.]),
+        ];
+        let mut frames = Vec::new();
+        for (angle, axis) in rotations.iter() {
+            let r = glm::rotation(*angle, &glm::Vec3::from(*axis).normalize());
+            let column = |i| glm::vec4_to_vec3(&glm::column(&r, i)).into();
+            for handedness in [1., -1.].iter() {
+                frames.push(vertices::PosUVNormTang {
+                    position: [1., -2., 3.],
+                    uv: [0., 0.],
+                    normal: column(2),
+                    tangent: column(0),
+                    handedness: *handedness,
+                });
+            }
+        }
+        frames
+    }
+
+    const BARYCENTRICS: [[f32; 3]; 3] = [[1., 0., 0.], [0.2, 0.3, 0.5], [0.6, 0.3, 0.1]];
+
+    const MAP_NORMALS: [[f32; 3]; 4] = [
+        [0., 0., 1.],
+        [0.6, 0., 0.8],
+        [0., 0.6, 0.8],
+        [0.48, -0.36, 0.8],
+    ];
+
+    /// The normal the TBN frame gives, written out.
+    fn expected_normal(frame: &vertices::PosUVNormTang, n: [f32; 3]) -> [f32; 3] {
+        let bitangent = scale3(frame.handedness, cross(frame.normal, frame.tangent));
+        normalize(add3(
+            add3(scale3(n[X], frame.tangent), scale3(n[Y], bitangent)),
+            scale3(n[Z], frame.normal),
+        ))
+    }
+
+    /// Decodes every frame as the same at all three vertices, so the
+    /// interpolation does not change it, and compares with the frame.
+    fn assert_decodes<V>(tolerance: f32)
+    where
+        V: Decode + From<vertices::PosUVNormTang>,
+    {
+        for frame in frames() {
+            let v = V::from(frame.clone());
+            for b in BARYCENTRICS.iter() {
+                for n in MAP_NORMALS.iter() {
+                    let decoded = V::fragment_normal([&v, &v, &v], *b, *n);
+                    let expected = expected_normal(&frame, *n);
+                    assert!(
+                        normal_distance(decoded, expected) <= tolerance,
+                        "{} decodes {:?} to {:?} for {:?}, expected {:?}",
+                        std::any::type_name::<V>(),
+                        n,
+                        decoded,
+                        frame,
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn matrix_decodes_known_frames() {
+        let frame = |handedness| vertices::PosUVNormTang {
+            position: [0., 0., 0.],
+            uv: [0., 0.],
+            normal: [0., 0., 1.],
+            tangent: [1., 0., 0.],
+            handedness,
+        };
+        let n = [0.48, -0.36, 0.8];
+        for (handedness, expected) in [(1., n), (-1., [0.48, 0.36, 0.8])].iter() {
+            let v = vertices::Matrix::from(frame(*handedness));
+            let decoded = vertices::Matrix::fragment_normal([&v, &v, &v], [0.2, 0.3, 0.5], n);
+            assert!(normal_distance(decoded, *expected) <= 1e-6);
+        }
+    }
+
+    #[test]
+    fn f32_layouts_decode_like_the_matrix() {
+        const TOLERANCE: f32 = 1e-4;
+        assert_decodes::<vertices::Matrix>(TOLERANCE);
+        assert_decodes::<vertices::PosUVNormTang>(TOLERANCE);
+        assert_decodes::<vertices::Rotor>(TOLERANCE);
+        assert_decodes::<vertices::Motor>(TOLERANCE);
+        assert_decodes::<vertices::OuterRotor>(TOLERANCE);
+        assert_decodes::<vertices::OuterMotor>(TOLERANCE);
+        assert_decodes::<vertices::CayleyRotor>(TOLERANCE);
+        assert_decodes::<vertices::CayleyMotor>(TOLERANCE);
+        assert_decodes::<vertices::QRotor>(TOLERANCE);
+        assert_decodes::<vertices::LogMotor>(TOLERANCE);
+    }
+
+    #[test]
+    fn packed_layouts_decode_like_the_matrix() {
+        assert_decodes::<packed::Rotor16>(1e-3);
+        assert_decodes::<packed::MotorHalf>(1e-3);
+        assert_decodes::<packed::Rotor8>(3e-2);
+        assert_decodes::<packed::QRotor1010102>(1e-2);
+    }
+
+    #[test]
+    fn every_encoding_of_all_decodes_like_the_matrix() {
+        for frame in frames() {
+            let v = vertices::All::from(frame.clone());
+            for encoding in Encoding::ALL.iter() {
+                let tolerance = match encoding {
+                    Encoding::Rotor16 | Encoding::MotorHalf => 1e-3,
+                    Encoding::Rotor8 => 3e-2,
+                    Encoding::QRotor1010102 => 1e-2,
+                    _ => 1e-4,
+                };
+                for b in BARYCENTRICS.iter() {
+                    for n in MAP_NORMALS.iter() {
+                        let decoded = fragment_normal(*encoding, [&v, &v, &v], *b, *n);
+                        let expected = expected_normal(&frame, *n);
+                        assert!(
+                            normal_distance(decoded, expected) <= tolerance,
+                            "{} decodes {:?} to {:?} for {:?}, expected {:?}",
+                            encoding.name(),
+                            n,
+                            decoded,
+                            frame,
+                            expected
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,674 @@
+//! Analysis of the benchmark results in `results.csv`. Rows are grouped by
+//! encoding and by the number of vertices per pixel, and every encoding
+//! is compared against a baseline (the matrix encoding by default). The
+//! result can be written as a Markdown table, an HTML page and an SVG bar
+//! chart. Two result sets can also be compared with [`compare`], to find
+//! the encodings that got significantly slower or faster.
+
+use crate::stats;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// The columns of a `shader_bench` record that the report uses. Rows
+/// written before the suites existed have no run id, encoding, workload,
+/// stage or decode placement, their encoding is the shader filename.
+#[derive(Debug, serde::Deserialize)]
+pub struct ResultRow {
+    #[serde(default)]
+    pub run_id: String,
+    #[serde(default)]
+    pub encoding: String,
+    pub filename: String,
+    #[serde(default)]
+    pub vertex: String,
+    #[serde(default)]
+    pub workload: String,
+    #[serde(default)]
+    pub stage: String,
+    #[serde(default)]
+    pub decode: String,
+    pub average_drawtime: f32,
+    pub std: f32,
+    #[serde(default)]
+    pub median_drawtime: Option<f32>,
+    /// Measured frames, rows from before the statistics module lack it.
+    #[serde(default)]
+    pub n_samples: Option<usize>,
+    pub n_vertices: usize,
+    /// Empty for the vertex stage.
+    #[serde(default)]
+    pub n_fragments: Option<usize>,
+    /// Size of the framebuffer, rows from before it was recorded only have
+    /// the fragments.
+    #[serde(default)]
+    pub n_pixels: Option<usize>,
+}
+
+impl ResultRow {
+    pub fn encoding(&self) -> &str {
+        if self.encoding.is_empty() {
+            &self.filename
+        } else {
+            &self.encoding
+        }
+    }
+
+    /// The workload, stage and decode placement, leaving out the default
+    /// grid, full draw and placement.
+    pub fn variant(&self) -> String {
+        [
+            self.workload.as_str(),
+            self.stage.as_str(),
+            self.decode.as_str(),
+        ]
+        .iter()
+        .filter(|v| !["", "grid", "full", "default"].contains(v))
+        .copied()
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+
+    /// The encoding, followed by the variant when there is one.
+    pub fn label(&self) -> String {
+        let variant = self.variant();
+        if variant.is_empty() {
+            self.encoding().to_string()
+        } else {
+            format!("{} ({})", self.encoding(), variant)
+        }
+    }
+
+    /// Size of the framebuffer, 0 when unknown.
+    fn framebuffer_size(&self) -> usize {
+        self.n_pixels.or(self.n_fragments).unwrap_or_default()
+    }
+
+    /// Vertices per pixel in hundredths, used to group the rows.
+    fn density_key(&self) -> u32 {
+        (self.n_vertices as f32 / self.framebuffer_size() as f32 * 100.).round() as u32
+    }
+}
+
+pub fn read_results(path: impl AsRef<std::path::Path>) -> Vec<ResultRow> {
+    let mut reader = csv::Reader::from_path(path).expect("Could not read results");
+    reader
+        .deserialize()
+        .map(|row| row.expect("Invalid row in results"))
+        .collect()
+}
+
+/// The rows of `run`, of the last run when it is `None` or of every run
+/// for `Some("all")`. Also returns the id of the selected run.
+pub fn select_run<'a>(rows: &'a [ResultRow], run: Option<&str>) -> (String, Vec<&'a ResultRow>) {
+    let run_id = match run {
+        Some(run) => run.to_string(),
+        None => rows.last().map(|r| r.run_id.clone()).unwrap_or_default(),
+    };
+    let rows = rows
+        .iter()
+        .filter(|r| run_id == "all" || r.run_id == run_id)
+        .collect();
+    (run_id, rows)
+}
+
+/// Which draw time of a record is compared.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Statistic {
+    Mean,
+    Median,
+}
+
+impl Statistic {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "mean" => Statistic::Mean,
+            "median" => Statistic::Median,
+            _ => panic!("Unknown statistic {}, use mean or median", name),
+        }
+    }
+
+    fn of(self, row: &ResultRow) -> Option<f32> {
+        match self {
+            Statistic::Mean => Some(row.average_drawtime),
+            Statistic::Median => row.median_drawtime,
+        }
+    }
+}
+
+pub struct EncodingResult {
+    pub encoding: String,
+    /// Draw time (ms) per density, `None` when it was not measured.
+    pub drawtimes: Vec<Option<f32>>,
+    /// Baseline draw time divided by this draw time, per density.
+    pub speedups: Vec<Option<f32>>,
+}
+
+pub struct Report {
+    pub run_id: String,
+    pub baseline: String,
+    pub statistic: Statistic,
+    /// Vertices per fragment of the columns.
+    pub densities: Vec<f32>,
+    /// Fastest encoding first.
+    pub results: Vec<EncodingResult>,
+}
+
+impl Report {
+    /// Builds the report of one run, the last one in `rows` when `run` is
+    /// `None`. Pass `Some("all")` to use every row. Rows of the same
+    /// encoding and density (like those of multiple resolutions) are
+    /// averaged. Encodings are compared with the baseline of the same
+    /// workload, stage and decode placement.
+    pub fn new(
+        rows: &[ResultRow],
+        run: Option<&str>,
+        baseline: &str,
+        statistic: Statistic,
+    ) -> Self {
+        let (run_id, rows) = select_run(rows, run);
+
+        let mut densities = rows.iter().map(|r| r.density_key()).collect::<Vec<_>>();
+        densities.sort_unstable();
+        densities.dedup();
+
+        // Summed draw time and count per variant, encoding and density.
+        let mut groups: BTreeMap<(String, &str), Vec<(f32, usize)>> = BTreeMap::new();
+        let mut order = Vec::new();
+        for row in rows.iter() {
+            let time = match statistic.of(row) {
+                Some(time) if time.is_finite() => time,
+                _ => continue,
+            };
+            let key = (row.variant(), row.encoding());
+            let group = groups.entry(key.clone()).or_insert_with(|| {
+                order.push((key, row.label()));
+                vec![(0., 0); densities.len()]
+            });
+            let i = densities.binary_search(&row.density_key()).unwrap();
+            group[i].0 += time;
+            group[i].1 += 1;
+        }
+        let drawtimes = |key: &(String, &str)| -> Vec<Option<f32>> {
+            groups[key]
+                .iter()
+                .map(|&(sum, n)| if n > 0 { Some(sum / n as f32) } else { None })
+                .collect()
+        };
+
+        if !groups.keys().any(|(_, encoding)| *encoding == baseline) {
+            eprintln!("Baseline {} is not in the results", baseline);
+        }
+        let mut results = order
+            .iter()
+            .map(|(key, label)| {
+                let baseline_key = (key.0.clone(), baseline);
+                let baseline_times = match groups.get(&baseline_key) {
+                    Some(_) => drawtimes(&baseline_key),
+                    None => vec![None; densities.len()],
+                };
+                let drawtimes = drawtimes(key);
+                let speedups = drawtimes
+                    .iter()
+                    .zip(baseline_times.iter())
+                    .map(|(t, b)| Some(b.as_ref()? / t.as_ref()?))
+                    .collect();
+                EncodingResult {
+                    encoding: label.clone(),
+                    drawtimes,
+                    speedups,
+                }
+            })
+            .collect::<Vec<_>>();
+        results.sort_by(|a, b| a.mean_drawtime().partial_cmp(&b.mean_drawtime()).unwrap());
+
+        Self {
+            run_id,
+            baseline: baseline.to_string(),
+            statistic,
+            densities: densities.iter().map(|d| *d as f32 / 100.).collect(),
+            results,
+        }
+    }
+
+    fn statistic_name(&self) -> &str {
+        match self.statistic {
+            Statistic::Mean => "Mean",
+            Statistic::Median => "Median",
+        }
+    }
+
+    fn header(&self) -> Vec<String> {
+        let mut header = vec!["Encoding".to_string()];
+        for density in self.densities.iter() {
+            header.push(format!(
+                "{} drawtime (ms), {} v/f",
+                self.statistic_name(),
+                density
+            ));
+            header.push(format!("Speed-up vs {}, {} v/f", self.baseline, density));
+        }
+        header
+    }
+
+    fn cells(&self, result: &EncodingResult) -> Vec<String> {
+        let mut cells = vec![result.encoding.clone()];
+        for (time, speedup) in result.drawtimes.iter().zip(result.speedups.iter()) {
+            cells.push(time.map_or("-".into(), |t| format!("{:.3}", t)));
+            cells.push(speedup.map_or("-".into(), |s| format!("{:.2}x", s)));
+        }
+        cells
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!("# Benchmark report\n\nRun `{}`.\n\n", self.run_id);
+        let header = self.header();
+        md += &format!("| {} |\n", header.join(" | "));
+        md += &format!("|{}\n", " --- |".repeat(header.len()));
+        for result in self.results.iter() {
+            md += &format!("| {} |\n", self.cells(result).join(" | "));
+        }
+        md
+    }
+
+    /// A page with the table and the bar chart.
+    pub fn to_html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Benchmark report</title>\n<style>\n\
+             body { font-family: sans-serif; }\n\
+             table { border-collapse: collapse; }\n\
+             th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: right; }\n\
+             td:first-child { text-align: left; }\n\
+             </style>\n</head>\n<body>\n<h1>Benchmark report</h1>\n",
+        );
+        writeln!(html, "<p>Run <code>{}</code>.</p>", escape(&self.run_id)).unwrap();
+        html += "<table>\n<tr>";
+        for h in self.header() {
+            write!(html, "<th>{}</th>", escape(&h)).unwrap();
+        }
+        html += "</tr>\n";
+        for result in self.results.iter() {
+            html += "<tr>";
+            for c in self.cells(result) {
+                write!(html, "<td>{}</td>", escape(&c)).unwrap();
+            }
+            html += "</tr>\n";
+        }
+        html += "</table>\n";
+        html += &self.to_svg();
+        html += "</body>\n</html>\n";
+        html
+    }
+
+    /// Horizontal bar chart of the draw times, a bar per density for every
+    /// encoding.
+    pub fn to_svg(&self) -> String {
+        const COLORS: [&str; 6] = [
+            "#4c72b0", "#dd8452", "#55a868", "#c44e52", "#8172b3", "#937860",
+        ];
+        const LABEL_WIDTH: f32 = 260.;
+        const CHART_WIDTH: f32 = 500.;
+        const BAR_HEIGHT: f32 = 14.;
+        const GROUP_GAP: f32 = 10.;
+        const TOP: f32 = 40.;
+        const BOTTOM: f32 = 40.;
+
+        let max_time = self
+            .results
+            .iter()
+            .flat_map(|r| r.drawtimes.iter().flatten())
+            .fold(0f32, |a, &b| a.max(b));
+        let scale = if max_time > 0. {
+            CHART_WIDTH / max_time
+        } else {
+            0.
+        };
+        let group_height = BAR_HEIGHT * self.densities.len() as f32 + GROUP_GAP;
+        let height = TOP + group_height * self.results.len() as f32 + BOTTOM;
+        let width = LABEL_WIDTH + CHART_WIDTH + 40.;
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+             font-family=\"sans-serif\" font-size=\"12\">",
+            width, height
+        )
+        .unwrap();
+
+        // Legend
+        for (i, density) in self.densities.iter().enumerate() {
+            let x = LABEL_WIDTH + i as f32 * 90.;
+            writeln!(
+                svg,
+                "<rect x=\"{}\" y=\"12\" width=\"12\" height=\"12\" fill=\"{}\"/>\
+                 <text x=\"{}\" y=\"22\">{} v/f</text>",
+                x,
+                COLORS[i % COLORS.len()],
+                x + 16.,
+                density
+            )
+            .unwrap();
+        }
+
+        for (i, result) in self.results.iter().enumerate() {
+            let y = TOP + i as f32 * group_height;
+            writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>",
+                LABEL_WIDTH - 8.,
+                y + BAR_HEIGHT * self.densities.len() as f32 / 2. + 4.,
+                escape(&result.encoding)
+            )
+            .unwrap();
+            for (j, time) in result.drawtimes.iter().enumerate() {
+                if let Some(time) = time {
+                    writeln!(
+                        svg,
+                        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\">\
+                         <title>{:.3} ms</title></rect>",
+                        LABEL_WIDTH,
+                        y + j as f32 * BAR_HEIGHT,
+                        time * scale,
+                        BAR_HEIGHT - 1.,
+                        COLORS[j % COLORS.len()],
+                        time
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        // Axis with five ticks
+        let axis_y = height - BOTTOM + 4.;
+        writeln!(
+            svg,
+            "<line x1=\"{0}\" y1=\"{1}\" x2=\"{2}\" y2=\"{1}\" stroke=\"black\"/>",
+            LABEL_WIDTH,
+            axis_y,
+            LABEL_WIDTH + CHART_WIDTH
+        )
+        .unwrap();
+        for tick in 0..=5 {
+            let time = max_time * tick as f32 / 5.;
+            let x = LABEL_WIDTH + time * scale;
+            writeln!(
+                svg,
+                "<line x1=\"{0}\" y1=\"{1}\" x2=\"{0}\" y2=\"{2}\" stroke=\"black\"/>\
+                 <text x=\"{0}\" y=\"{3}\" text-anchor=\"middle\">{4:.2}</text>",
+                x,
+                axis_y,
+                axis_y + 4.,
+                axis_y + 16.,
+                time
+            )
+            .unwrap();
+        }
+        writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{} Drawtime (ms)</text>",
+            LABEL_WIDTH + CHART_WIDTH / 2.,
+            axis_y + 32.,
+            self.statistic_name()
+        )
+        .unwrap();
+        svg += "</svg>\n";
+        svg
+    }
+}
+
+impl EncodingResult {
+    fn mean_drawtime(&self) -> f32 {
+        let measured = self.drawtimes.iter().flatten().collect::<Vec<_>>();
+        if measured.is_empty() {
+            return f32::INFINITY;
+        }
+        measured.iter().copied().sum::<f32>() / measured.len() as f32
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    Regression,
+    Improvement,
+    /// Not significant, or smaller than the threshold.
+    Unchanged,
+    /// Too few samples in one of the rows to test.
+    Untestable,
+}
+
+impl Verdict {
+    pub fn name(self) -> &'static str {
+        match self {
+            Verdict::Regression => "regression",
+            Verdict::Improvement => "improvement",
+            Verdict::Unchanged => "unchanged",
+            Verdict::Untestable => "untestable",
+        }
+    }
+}
+
+pub struct Difference {
+    pub encoding: String,
+    pub n_vertices: usize,
+    pub n_fragments: Option<usize>,
+    pub baseline: f32,
+    pub candidate: f32,
+    /// Relative change of the mean draw time, positive is slower.
+    pub change: f32,
+    pub p: f32,
+    pub verdict: Verdict,
+}
+
+/// Compares the mean draw times of every encoding, workload, stage, decode
+/// placement, mesh size and framebuffer size that is in both result sets.
+/// A change is significant when Welch's t-test gives a p-value below
+/// `alpha`, and only counts when it is larger than `threshold` (relative
+/// to the baseline).
+pub fn compare(
+    baseline: &[&ResultRow],
+    candidate: &[&ResultRow],
+    alpha: f32,
+    threshold: f32,
+) -> Vec<Difference> {
+    candidate
+        .iter()
+        .filter_map(|c| {
+            let b = baseline.iter().find(|b| {
+                b.encoding() == c.encoding()
+                    && b.workload == c.workload
+                    && b.stage == c.stage
+                    && b.decode == c.decode
+                    && b.n_vertices == c.n_vertices
+                    && b.framebuffer_size() == c.framebuffer_size()
+            })?;
+            let change = (c.average_drawtime - b.average_drawtime) / b.average_drawtime;
+            let (p, verdict) = match (b.n_samples, c.n_samples) {
+                (Some(n_b), Some(n_c)) if n_b > 1 && n_c > 1 => {
+                    let test = stats::welch_t_test(
+                        (c.average_drawtime, c.std, n_c),
+                        (b.average_drawtime, b.std, n_b),
+                    );
+                    let significant = test.p < alpha && change.abs() > threshold;
+                    let verdict = if !significant {
+                        Verdict::Unchanged
+                    } else if change > 0. {
+                        Verdict::Regression
+                    } else {
+                        Verdict::Improvement
+                    };
+                    (test.p, verdict)
+                }
+                _ => (f32::NAN, Verdict::Untestable),
+            };
+            Some(Difference {
+                encoding: c.label(),
+                n_vertices: c.n_vertices,
+                n_fragments: c.n_fragments,
+                baseline: b.average_drawtime,
+                candidate: c.average_drawtime,
+                change,
+                p,
+                verdict,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(encoding: &str, n_vertices: usize, n_pixels: usize, drawtime: f32) -> ResultRow {
+        ResultRow {
+            run_id: "run".into(),
+            encoding: encoding.into(),
+            filename: format!("shaders/{}.glsl", encoding),
+            vertex: encoding.into(),
+            workload: "grid".into(),
+            stage: "full".into(),
+            decode: "default".into(),
+            average_drawtime: drawtime,
+            std: 0.1,
+            median_drawtime: Some(drawtime),
+            n_samples: Some(100),
+            n_vertices,
+            n_fragments: Some(n_pixels),
+            n_pixels: Some(n_pixels),
+        }
+    }
+
+    #[test]
+    fn report_groups_by_density_and_divides_by_the_baseline() {
+        let rows = [
+            row("matrix", 500, 1000, 2.),
+            row("matrix", 1000, 1000, 4.),
+            row("rotor", 500, 1000, 1.),
+            // Same density at another resolution, averaged with the above.
+            row("rotor", 1000, 2000, 1.5),
+            row("rotor", 1000, 1000, 2.),
+        ];
+        let report = Report::new(&rows, None, "matrix", Statistic::Mean);
+
+        assert_eq!(report.densities, vec![0.5, 1.]);
+        assert_eq!(report.results[0].encoding, "rotor");
+        assert_eq!(report.results[0].drawtimes, vec![Some(1.25), Some(2.)]);
+        assert_eq!(report.results[0].speedups, vec![Some(1.6), Some(2.)]);
+        assert_eq!(report.results[1].encoding, "matrix");
+        assert_eq!(report.results[1].speedups, vec![Some(1.), Some(1.)]);
+        assert!(report
+            .to_markdown()
+            .contains("| rotor | 1.250 | 1.60x | 2.000 | 2.00x |"));
+    }
+
+    #[test]
+    fn speedup_is_missing_without_a_baseline_row() {
+        let rows = [row("matrix", 500, 1000, 2.), row("rotor", 1000, 1000, 2.)];
+        let report = Report::new(&rows, None, "matrix", Statistic::Mean);
+
+        let rotor = report
+            .results
+            .iter()
+            .find(|r| r.encoding == "rotor")
+            .unwrap();
+        assert_eq!(rotor.speedups, vec![None, None]);
+        assert!(report
+            .to_markdown()
+            .contains("| rotor | - | - | 2.000 | - |"));
+    }
+
+    #[test]
+    fn labels_are_escaped_in_html_and_svg() {
+        let rows = [row("matrix", 500, 1000, 2.), row("<b>&", 500, 1000, 1.)];
+        let report = Report::new(&rows, None, "matrix", Statistic::Mean);
+
+        for output in [report.to_html(), report.to_svg()].iter() {
+            assert!(output.contains("&lt;b&gt;&amp;"), "{}", output);
+            assert!(!output.contains("<b>"), "{}", output);
+        }
+    }
+
+    fn verdicts(baseline: &[ResultRow], candidate: &[ResultRow]) -> Vec<(String, Verdict)> {
+        let baseline = baseline.iter().collect::<Vec<_>>();
+        let candidate = candidate.iter().collect::<Vec<_>>();
+        compare(&baseline, &candidate, 0.05, 0.05)
+            .into_iter()
+            .map(|d| (d.encoding, d.verdict))
+            .collect()
+    }
+
+    #[test]
+    fn compare_flags_significant_changes_above_the_threshold() {
+        let baseline = [
+            row("matrix", 500, 1000, 2.),
+            row("rotor", 500, 1000, 2.),
+            row("motor", 500, 1000, 2.),
+        ];
+        let candidate = [
+            row("matrix", 500, 1000, 2.5),
+            row("rotor", 500, 1000, 1.5),
+            // Significant, but a change of 3%.
+            row("motor", 500, 1000, 2.06),
+        ];
+        assert_eq!(
+            verdicts(&baseline, &candidate),
+            vec![
+                ("matrix".to_string(), Verdict::Regression),
+                ("rotor".to_string(), Verdict::Improvement),
+                ("motor".to_string(), Verdict::Unchanged),
+            ]
+        );
+    }
+
+    #[test]
+    fn compare_ignores_noisy_changes() {
+        let baseline = [row("matrix", 500, 1000, 2.)];
+        let mut candidate = [row("matrix", 500, 1000, 2.5)];
+        candidate[0].std = 10.;
+        let differences = compare(&[&baseline[0]], &[&candidate[0]], 0.05, 0.05);
+        assert_eq!(differences[0].verdict, Verdict::Unchanged);
+        assert!(differences[0].p > 0.05);
+        assert!((differences[0].change - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn compare_skips_unmatched_rows() {
+        let baseline = [row("matrix", 500, 1000, 2.)];
+        let mut candidates = vec![
+            row("rotor", 500, 1000, 4.),
+            row("matrix", 1000, 1000, 4.),
+            row("matrix", 500, 2000, 4.),
+        ];
+        for &(workload, stage, decode) in [
+            ("sphere", "full", "default"),
+            ("grid", "vertex", "default"),
+            ("grid", "full", "hybrid"),
+        ]
+        .iter()
+        {
+            let mut r = row("matrix", 500, 1000, 4.);
+            r.workload = workload.into();
+            r.stage = stage.into();
+            r.decode = decode.into();
+            candidates.push(r);
+        }
+        assert!(verdicts(&baseline, &candidates).is_empty());
+    }
+
+    #[test]
+    fn compare_without_samples_is_untestable() {
+        let mut baseline = [row("matrix", 500, 1000, 2.)];
+        baseline[0].n_samples = None;
+        let candidate = [row("matrix", 500, 1000, 4.)];
+        let differences = compare(&[&baseline[0]], &[&candidate[0]], 0.05, 0.05);
+        assert_eq!(differences[0].verdict, Verdict::Untestable);
+        assert!(differences[0].p.is_nan());
+    }
+}
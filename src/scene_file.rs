@@ -0,0 +1,200 @@
+//! Scenes for the app, read from a TOML file like `scene.toml`. A scene
+//! lists the meshes to load, the textures and materials they are drawn
+//! with, the light and where the camera starts, so a new asset does not
+//! need a recompile.
+//!
+//! ```toml
+//! [camera]
+//! distance = 4.0
+//!
+//! [[texture]]
+//! name = "Bricks"
+//! path = "imgs/brick_normals.png"
+//! kind = "normal-map"
+//!
+//! [[material]]
+//! name = "Brick Wall"
+//! specular = 0.2
+//! normal_map = "Bricks"
+//!
+//! [[mesh]]
+//! path = "models/gun/Handgun_obj.obj"
+//! group = "Gun"
+//! material = "Brick Wall"
+//!
+//! [[mesh]]
+//! shape = "sphere"
+//! active = true
+//! ```
+
+use crate::{material, techniques};
+use serde::Deserialize;
+
+/// The shapes a mesh can be instead of a file.
+pub const SHAPES: [&str; 5] = ["sphere", "cylinder", "rect", "grid", "monkey saddle"];
+
+#[derive(Deserialize)]
+pub struct SceneFile {
+    #[serde(default)]
+    pub camera: Camera,
+    #[serde(default)]
+    pub light: Light,
+    #[serde(default, rename = "material")]
+    pub materials: Vec<Material>,
+    #[serde(default, rename = "texture")]
+    pub textures: Vec<Texture>,
+    #[serde(default, rename = "mesh")]
+    pub meshes: Vec<MeshSource>,
+}
+
+/// Where the cameras start, the inspector by its orbit around the origin
+/// and the flying camera by its position.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Camera {
+    /// Starts with the flying camera instead of the inspector.
+    pub flying: bool,
+    pub distance: f32,
+    pub longitude: f32,
+    pub latitude: f32,
+    pub position: [f32; 3],
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            flying: false,
+            distance: 4.,
+            longitude: 0.,
+            latitude: 0.,
+            position: [0., 0., 4.],
+        }
+    }
+}
+
+/// The light that follows the camera, see `lights::CameraFollowingLight`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Light {
+    pub color: [f32; 3],
+    pub follows_mouse: bool,
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Self {
+            color: [0.8, 0.8, 0.8],
+            follows_mouse: true,
+        }
+    }
+}
+
+/// A texture for the materials, they can also be selected in the GUI.
+/// Textures of different kinds can have the same name.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Texture {
+    pub name: String,
+    pub path: String,
+    pub kind: techniques::Texture,
+}
+
+/// A material that meshes refer to by name. The one named `default` is
+/// used for meshes without a material, unless their file has its own.
+#[derive(Clone, Deserialize)]
+pub struct Material {
+    pub name: String,
+    #[serde(flatten)]
+    pub parameters: material::Material,
+    /// Names of textures of the matching kind.
+    #[serde(default)]
+    pub albedo_map: Option<String>,
+    #[serde(default)]
+    pub normal_map: Option<String>,
+    #[serde(default)]
+    pub roughness_map: Option<String>,
+}
+
+impl Material {
+    /// The names of the textures it uses with their kinds.
+    pub fn maps(&self) -> impl Iterator<Item = (techniques::Texture, &String)> {
+        vec![
+            (techniques::Texture::Albedo, &self.albedo_map),
+            (techniques::Texture::NormalMap, &self.normal_map),
+            (techniques::Texture::Roughness, &self.roughness_map),
+        ]
+        .into_iter()
+        .filter_map(|(kind, name)| Some((kind, name.as_ref()?)))
+    }
+}
+
+/// A mesh file or one of the built in shapes.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MeshSource {
+    /// An obj, gltf or glb file.
+    #[serde(default)]
+    pub path: Option<String>,
+    /// One of [`SHAPES`].
+    #[serde(default)]
+    pub shape: Option<String>,
+    /// The name of the file or shape when left out.
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Drawn from the start.
+    #[serde(default)]
+    pub active: bool,
+    /// The name of a material, replaces the materials in the file.
+    #[serde(default)]
+    pub material: Option<String>,
+}
+
+impl MeshSource {
+    pub fn group(&self) -> String {
+        match (&self.group, &self.path, &self.shape) {
+            (Some(group), _, _) => group.clone(),
+            (None, Some(path), _) => std::path::Path::new(path)
+                .file_stem()
+                .map_or(path.clone(), |stem| stem.to_string_lossy().into_owned()),
+            (None, None, Some(shape)) => shape.clone(),
+            (None, None, None) => unreachable!(),
+        }
+    }
+}
+
+impl SceneFile {
+    pub fn from_path(path: impl AsRef<std::path::Path>) -> Self {
+        let source = std::fs::read_to_string(path).expect("Could not read scene file");
+        let scene: SceneFile = toml::from_str(&source).expect("Invalid scene file");
+        for material in scene.materials.iter() {
+            for (kind, name) in material.maps() {
+                if !scene
+                    .textures
+                    .iter()
+                    .any(|t| t.kind == kind && &t.name == name)
+                {
+                    panic!(
+                        "Unknown texture {} in material {}, add it as a [[texture]] of kind {}",
+                        name,
+                        material.name,
+                        kind.name()
+                    );
+                }
+            }
+        }
+        for mesh in scene.meshes.iter() {
+            if let Some(material) = &mesh.material {
+                if !scene.materials.iter().any(|m| &m.name == material) {
+                    panic!("Unknown material {}, add it as a [[material]]", material);
+                }
+            }
+            if mesh.path.is_some() == mesh.shape.is_some() {
+                panic!("Mesh {:?} needs either a path or a shape", mesh);
+            }
+            if let Some(shape) = &mesh.shape {
+                if !SHAPES.contains(&shape.as_str()) {
+                    panic!("Unknown shape {}, use one of {:?}", shape, SHAPES);
+                }
+            }
+        }
+        scene
+    }
+}
@@ -1,8 +1,8 @@
 use crate::material::Material;
-use crate::{cameras::Eye, lights};
+use crate::techniques::{Technique, Texture};
+use crate::{cameras::Eye, glsl, instancing, lights};
 use pgl::buffer::{Buffer, BufferType, DrawType};
 use pgl::shader::ShaderProgram;
-use pgl::window;
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -22,8 +22,14 @@ where
         self.borrow().bind()
     }
     fn set_model(&self, model: &glm::Mat4) {
+        warn_if_not_rigid(model);
         let mut shader = self.borrow_mut();
         shader.set_mat4fs("uModel", std::slice::from_ref(model));
+        let normal_matrix = glm::inverse_transpose(*model);
+        shader.set_mat4fs("uNormalMatrix", std::slice::from_ref(&normal_matrix));
+        let motor = model_motor(model);
+        shader.set_vec4fs("uModelMotorP1", std::slice::from_ref(&motor[0]));
+        shader.set_vec4fs("uModelMotorP2", std::slice::from_ref(&motor[1]));
     }
     fn set_material(&self, m: &Material) {
         let mut shader = self.borrow_mut();
@@ -34,6 +40,59 @@ where
     }
 }
 
+/// The rigid part of a model matrix as a motor, in the layout the shaders
+/// expect. Scaling is dropped, the motor encodings can not represent it.
+pub fn model_motor(model: &glm::Mat4) -> [[f32; 4]; 2] {
+    let axis = |i| glm::vec4_to_vec3(&glm::column(model, i)).normalize();
+    let rotor = ppga::Rotor::from_base(&axis(0).into(), &axis(1).into(), &axis(2).into());
+    let translation: [f32; 3] = glm::vec4_to_vec3(&glm::column(model, 3)).into();
+    ppga::Translator::new(&translation)
+        .mul_rotor(&rotor.normalize())
+        .normalize()
+        .into_klein()
+}
+
+/// Loads the shader at `path` with the includes expanded by this crate, so
+/// they get the attribute blocks of the current vertex layouts (see
+/// `glsl::inline`). `pgl` reads the result from a temporary file, which is
+/// removed again.
+pub fn load(
+    path: impl AsRef<std::path::Path>,
+    options: pgl::shader::ShaderOptions,
+) -> Result<ShaderProgram, String> {
+    let path = path.as_ref();
+    let source = glsl::inline(path)?;
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let inlined =
+        std::env::temp_dir().join(format!("pthesis-{}-{}", std::process::id(), file_name));
+    std::fs::write(&inlined, source)
+        .map_err(|e| format!("Could not write {}: {}", inlined.display(), e))?;
+    let program = ShaderProgram::from_path(&inlined, options).map_err(|e| e.to_string());
+    let _ = std::fs::remove_file(&inlined);
+    program.map_err(|log| format!("{}:\n{}", path.display(), log))
+}
+
+/// The motor encodings only get the rigid part of the model matrix (see
+/// [`model_motor`]), so they shade scaled, sheared or mirrored models
+/// differently from the matrix encodings. Warns once when that happens, the
+/// glTF loader bakes such transforms into the vertices instead.
+fn warn_if_not_rigid(model: &glm::Mat4) {
+    const RIGID_EPSILON: f32 = 1e-3;
+    let linear = glm::mat4_to_mat3(model);
+    let error = (linear.transpose() * linear - glm::Mat3::identity())
+        .abs()
+        .max();
+    if error > RIGID_EPSILON || linear.determinant() < 0. {
+        static WARN: std::sync::Once = std::sync::Once::new();
+        WARN.call_once(|| {
+            eprintln!(
+                "Warning: a model matrix is not rigid, the motor encodings ignore its \
+                 scaling, shearing and mirroring"
+            )
+        });
+    }
+}
+
 macro_rules! impl_deref_shader {
     ($name:ty) => {
         impl std::ops::Deref for $name {
@@ -44,11 +103,8 @@ macro_rules! impl_deref_shader {
         }
     };
 }
-impl_deref_shader!(NormalMapping);
+impl_deref_shader!(TechniqueShader);
 impl_deref_shader!(Ui);
-impl_deref_shader!(Flat);
-impl_deref_shader!(Textured);
-impl_deref_shader!(NormalAlbedoMapping);
 
 const MAX_POINT_LIGHTS: usize = 1;
 
@@ -100,7 +156,7 @@ impl AppUniforms {
     pub fn update(
         &mut self,
         eye: &dyn Eye,
-        win: &window::GlfwWindow,
+        aspect: f32,
         light_pos: [f32; 3],
         light_color: [f32; 3],
     ) {
@@ -110,7 +166,6 @@ impl AppUniforms {
         u.projection = eye.projection();
         u.view = eye.view();
         const ORTHO_RAD: f32 = 4.;
-        let aspect = win.aspect();
         u.ortho = glm::ortho(
             -ORTHO_RAD,
             ORTHO_RAD,
@@ -124,93 +179,138 @@ impl AppUniforms {
     }
 }
 
-// ALL SHADERS
-//
-// These are basically compisition of the ShaderProgram struct,
-// each having their own personal functions for setting uniforms.
-
-#[derive(Clone)]
-pub struct NormalMapping {
-    s: Rc<RefCell<ShaderProgram>>,
+/// Where the normal mapping shaders decode the tangent frame, selected
+/// with a `DECODE_*` define in both stages.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DecodePlacement {
+    /// What the shader does without a define: the rotor and motor shaders
+    /// are hybrid, the logarithm, Cayley and QTangent shaders decode per
+    /// fragment and the matrix shaders interpolate the matrix.
+    Default,
+    /// Decode in the vertex shader and interpolate the tangent to world
+    /// matrix.
+    PerVertex,
+    /// Interpolate the compact parameters and decode in the fragment
+    /// shader.
+    PerFragment,
+    /// Decode to a rotor or motor in the vertex shader, interpolate that
+    /// and apply it in the fragment shader.
+    Hybrid,
 }
 
-impl NormalMapping {
-    pub fn new(p: impl AsRef<std::path::Path>) -> Self {
-        let s = ShaderProgram::from_path(p, Default::default()).unwrap();
-        s.bind();
-        s.bind_uniform_block("App", 0);
-        Self {
-            s: Rc::new(RefCell::new(s)),
+impl DecodePlacement {
+    pub const ALL: [DecodePlacement; 4] = [
+        DecodePlacement::Default,
+        DecodePlacement::PerVertex,
+        DecodePlacement::PerFragment,
+        DecodePlacement::Hybrid,
+    ];
+
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "default" => DecodePlacement::Default,
+            "per-vertex" => DecodePlacement::PerVertex,
+            "per-fragment" => DecodePlacement::PerFragment,
+            "hybrid" => DecodePlacement::Hybrid,
+            _ => panic!(
+                "Unknown decode placement {}, use default, per-vertex, per-fragment or hybrid",
+                name
+            ),
         }
     }
-    pub fn set_uniforms(&mut self, normal_map: i32) {
-        let mut s = self.s.borrow_mut();
-        s.set_int("uNormalMap", normal_map);
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DecodePlacement::Default => "default",
+            DecodePlacement::PerVertex => "per-vertex",
+            DecodePlacement::PerFragment => "per-fragment",
+            DecodePlacement::Hybrid => "hybrid",
+        }
     }
-}
 
-#[derive(Clone)]
-pub struct NormalAlbedoMapping {
-    s: Rc<RefCell<ShaderProgram>>,
-}
+    /// Shown in the app.
+    pub fn label(&self) -> &'static str {
+        match self {
+            DecodePlacement::Default => "Shader Default",
+            DecodePlacement::PerVertex => "Per Vertex",
+            DecodePlacement::PerFragment => "Per Fragment",
+            DecodePlacement::Hybrid => "Hybrid",
+        }
+    }
 
-impl NormalAlbedoMapping {
-    pub fn new() -> Self {
-        let s = ShaderProgram::from_path("shaders/nm_tex.glsl", Default::default()).unwrap();
-        s.bind();
-        s.bind_uniform_block("App", 0);
-        Self {
-            s: Rc::new(RefCell::new(s)),
+    pub fn define(&self) -> Option<&'static str> {
+        match self {
+            DecodePlacement::Default => None,
+            DecodePlacement::PerVertex => Some("DECODE_PER_VERTEX"),
+            DecodePlacement::PerFragment => Some("DECODE_PER_FRAGMENT"),
+            DecodePlacement::Hybrid => Some("DECODE_HYBRID"),
         }
     }
-    pub fn set_uniforms(&mut self, normal_map: i32, albedo_map: i32) {
-        let mut s = self.s.borrow_mut();
-        s.set_int("uNormalMap", normal_map);
-        s.set_int("uAlbedoMap", albedo_map);
+
+    /// Shader options with the define in both stages.
+    pub fn shader_options(&self) -> pgl::shader::ShaderOptions {
+        let defines = self
+            .define()
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        pgl::shader::ShaderOptions {
+            vs_defines: defines.clone(),
+            fs_defines: defines,
+            ..Default::default()
+        }
     }
 }
 
+// ALL SHADERS
+//
+// These are basically compisition of the ShaderProgram struct,
+// each having their own personal functions for setting uniforms.
+
+/// The shader of a technique from the registry, see `techniques`.
 #[derive(Clone)]
-pub struct Textured {
+pub struct TechniqueShader {
     s: Rc<RefCell<ShaderProgram>>,
+    pub technique: &'static Technique,
 }
 
-impl Textured {
-    pub fn new() -> Self {
-        //let s =
-        //ShaderProgram::from_frag_and_vert_path("shaders/tex.frag", "shaders/tex.vert").unwrap();
-        let s = ShaderProgram::from_path("shaders/tex.glsl", Default::default()).unwrap();
-        s.bind();
-        s.bind_uniform_block("App", 0);
+impl TechniqueShader {
+    pub fn new(technique: &'static Technique, placement: DecodePlacement) -> Self {
+        let s = Self::compile(technique, placement).unwrap();
         Self {
             s: Rc::new(RefCell::new(s)),
+            technique,
         }
     }
-    pub fn set_uniforms(&mut self, albedo_map: i32) {
-        let mut s = self.s.borrow_mut();
-        s.set_int("uAlbedoMap", albedo_map);
+    /// Compiles the shader again. When that fails the old program is kept
+    /// and the compile log is returned.
+    pub fn reload(&self, placement: DecodePlacement) -> Result<(), String> {
+        let s = Self::compile(self.technique, placement)?;
+        *self.s.borrow_mut() = s;
+        Ok(())
     }
-}
-
-#[derive(Clone)]
-pub struct Flat {
-    s: Rc<RefCell<ShaderProgram>>,
-}
-
-impl Flat {
-    pub fn new() -> Self {
-        let s = ShaderProgram::from_path("shaders/flat.glsl", Default::default()).unwrap();
+    fn compile(
+        technique: &'static Technique,
+        placement: DecodePlacement,
+    ) -> Result<ShaderProgram, String> {
+        let mut options = placement.shader_options();
+        for define in technique.defines.iter().chain(&[instancing::DEFINE]) {
+            options.vs_defines.push(define.to_string());
+            options.fs_defines.push(define.to_string());
+        }
+        let s = load(technique.shader, options)?;
         s.bind();
         s.bind_uniform_block("App", 0);
-        Self {
-            s: Rc::new(RefCell::new(s)),
-        }
+        instancing::bind_block(&s);
+        Ok(s)
     }
-
-    pub fn set_uniforms(&mut self, albedo: &glm::Vec4) {
+    /// Points the samplers of the technique to the slots given by `slot`.
+    pub fn set_uniforms(&mut self, slot: impl Fn(Texture) -> i32) {
         let mut s = self.s.borrow_mut();
-        s.bind();
-        s.set_vec4fs("uMaterial.albedo", std::slice::from_ref(albedo));
+        for texture in self.technique.textures.iter() {
+            s.set_int(texture.uniform(), slot(*texture));
+        }
     }
 }
 
@@ -0,0 +1,146 @@
+//! Skeletal animation. A skeleton is a hierarchy of joints, posed by a
+//! local transform per joint. Every vertex is moved by up to four joints
+//! (see [`Influences`]), with the bone transform of each: its posed
+//! transform after the inverse of its rest transform. The skinned layouts
+//! in `vertices` carry the influences, the shaders get the bones as motors
+//! for dual quaternion skinning (`shaders/nm_skin_motor.glsl`, which
+//! composes the blended motor with the tangent motor of the vertex) or as
+//! matrices for linear blend skinning (`shaders/nm_skin_matrix.glsl`).
+
+use crate::shaders;
+
+/// Length of the bone arrays in `shaders/headers/skinning.glsl`.
+pub const MAX_BONES: usize = 32;
+
+/// Joints of the [`Skeleton::chain`] that the skinned layouts are built
+/// for.
+pub const CHAIN_JOINTS: usize = 8;
+
+pub struct Joint {
+    pub name: String,
+    /// Comes earlier in the skeleton than the joint.
+    pub parent: Option<usize>,
+    /// Relative to the parent, in the rest pose.
+    pub rest: glm::Mat4,
+}
+
+pub struct Skeleton {
+    pub joints: Vec<Joint>,
+    /// From model space to the space of every joint in the rest pose.
+    inverse_bind: Vec<glm::Mat4>,
+}
+
+impl Skeleton {
+    pub fn new(joints: Vec<Joint>) -> Self {
+        if joints.len() > MAX_BONES {
+            panic!(
+                "Skeleton has {} joints, at most {} are supported",
+                joints.len(),
+                MAX_BONES
+            );
+        }
+        for (i, joint) in joints.iter().enumerate() {
+            if matches!(joint.parent, Some(parent) if parent >= i) {
+                panic!("Joint {} comes before its parent", joint.name);
+            }
+        }
+        let mut skeleton = Self {
+            joints,
+            inverse_bind: Vec::new(),
+        };
+        skeleton.inverse_bind = skeleton
+            .globals(&skeleton.rest_pose())
+            .iter()
+            .map(glm::inverse)
+            .collect();
+        skeleton
+    }
+
+    /// A chain of [`CHAIN_JOINTS`] joints on the x axis from -1 to 1, a
+    /// tail through the meshes of the benchmark.
+    pub fn chain() -> Self {
+        let step = 2. / (CHAIN_JOINTS - 1) as f32;
+        let joints = (0..CHAIN_JOINTS)
+            .map(|i| Joint {
+                name: format!("Chain {}", i),
+                parent: if i == 0 { None } else { Some(i - 1) },
+                rest: if i == 0 {
+                    glm::translation(&glm::vec3(-1., 0., 0.))
+                } else {
+                    glm::translation(&glm::vec3(step, 0., 0.))
+                },
+            })
+            .collect();
+        Self::new(joints)
+    }
+
+    pub fn rest_pose(&self) -> Vec<glm::Mat4> {
+        self.joints.iter().map(|j| j.rest).collect()
+    }
+
+    /// The rest pose with every joint also rotated by `angle` around
+    /// `axis`, which curls a chain.
+    pub fn bent(&self, angle: f32, axis: &glm::Vec3) -> Vec<glm::Mat4> {
+        self.joints
+            .iter()
+            .map(|j| glm::rotate(&j.rest, angle, axis))
+            .collect()
+    }
+
+    /// The model space transform of every joint, for a local transform per
+    /// joint.
+    pub fn globals(&self, pose: &[glm::Mat4]) -> Vec<glm::Mat4> {
+        let mut globals: Vec<glm::Mat4> = Vec::with_capacity(self.joints.len());
+        for (joint, local) in self.joints.iter().zip(pose.iter()) {
+            let global = match joint.parent {
+                Some(parent) => globals[parent] * local,
+                None => *local,
+            };
+            globals.push(global);
+        }
+        globals
+    }
+
+    /// Moves vertices from the rest pose to `pose`, for linear blend
+    /// skinning.
+    pub fn bone_matrices(&self, pose: &[glm::Mat4]) -> Vec<glm::Mat4> {
+        self.globals(pose)
+            .iter()
+            .zip(self.inverse_bind.iter())
+            .map(|(global, inverse_bind)| global * inverse_bind)
+            .collect()
+    }
+
+    /// The bone matrices as motors in the layout the shaders expect, p1
+    /// and p2 after each other. Scaling is dropped, as for the model
+    /// motor.
+    pub fn bone_motors(&self, pose: &[glm::Mat4]) -> Vec<[f32; 4]> {
+        self.bone_matrices(pose)
+            .iter()
+            .flat_map(|bone| shaders::model_motor(bone).to_vec())
+            .collect()
+    }
+}
+
+/// The joints that move a vertex, as floats because the layouts only have
+/// float attributes, and how much. The weights add up to one.
+#[derive(Debug, Clone, Copy)]
+pub struct Influences {
+    pub joints: [f32; 4],
+    pub weights: [f32; 4],
+}
+
+impl Influences {
+    /// Blends the two joints of [`Skeleton::chain`] on either side of the
+    /// position, the vertices beyond the ends follow the end joints.
+    pub fn chain(position: [f32; 3]) -> Self {
+        let last = (CHAIN_JOINTS - 1) as f32;
+        let t = ((position[0] + 1.) / 2. * last).max(0.).min(last);
+        let joint = t.floor().min(last - 1.);
+        let blend = t - joint;
+        Self {
+            joints: [joint, joint + 1., 0., 0.],
+            weights: [1. - blend, blend, 0., 0.],
+        }
+    }
+}
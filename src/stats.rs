@@ -0,0 +1,281 @@
+//! Summary statistics for the benchmark timings. Frame times are noisy and
+//! skewed (the occasional frame that waits on the driver or compositor),
+//! so next to the mean this gives robust measures, percentiles and a
+//! confidence interval, after rejecting outliers with Tukey's fences.
+//! Two runs are compared with Welch's t-test.
+
+/// Fraction cut off at both ends for the trimmed mean.
+pub const TRIM: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Summary {
+    /// Samples left after outlier rejection.
+    pub n: usize,
+    pub n_outliers: usize,
+    pub mean: f32,
+    pub median: f32,
+    /// Mean of the samples without the lowest and highest [`TRIM`].
+    pub trimmed_mean: f32,
+    /// Sample standard deviation.
+    pub std: f32,
+    pub min: f32,
+    pub max: f32,
+    pub p95: f32,
+    pub p99: f32,
+    /// 95% confidence interval of the mean.
+    pub ci_low: f32,
+    pub ci_high: f32,
+}
+
+/// Summarizes `samples`. Samples outside `[q1 - k * iqr, q3 + k * iqr]`
+/// are rejected first, where `k` is `outlier_iqr`. Pass `None` to keep
+/// every sample. NaNs are always rejected, they count as outliers.
+pub fn summarize(samples: &[f32], outlier_iqr: Option<f32>) -> Summary {
+    let mut sorted = samples
+        .iter()
+        .copied()
+        .filter(|s| !s.is_nan())
+        .collect::<Vec<_>>();
+    sorted.sort_by(f32::total_cmp);
+    if let Some(k) = outlier_iqr {
+        let q1 = percentile(&sorted, 0.25);
+        let q3 = percentile(&sorted, 0.75);
+        let iqr = q3 - q1;
+        let (low, high) = (q1 - k * iqr, q3 + k * iqr);
+        sorted.retain(|s| *s >= low && *s <= high);
+    }
+    let n = sorted.len();
+
+    let mean = mean(&sorted);
+    let std = std(&sorted, mean);
+    let half_width = if n > 1 {
+        t_critical_95(n - 1) * std / (n as f32).sqrt()
+    } else {
+        f32::NAN
+    };
+    let trim = (n as f32 * TRIM) as usize;
+    Summary {
+        n,
+        n_outliers: samples.len() - n,
+        mean,
+        median: percentile(&sorted, 0.5),
+        trimmed_mean: self::mean(&sorted[trim..n - trim]),
+        std,
+        min: sorted.first().copied().unwrap_or(f32::NAN),
+        max: sorted.last().copied().unwrap_or(f32::NAN),
+        p95: percentile(&sorted, 0.95),
+        p99: percentile(&sorted, 0.99),
+        ci_low: mean - half_width,
+        ci_high: mean + half_width,
+    }
+}
+
+/// Nearest rank percentile of sorted samples, NaN when there are none.
+pub fn percentile(sorted: &[f32], p: f32) -> f32 {
+    if sorted.is_empty() {
+        return f32::NAN;
+    }
+    let i = ((sorted.len() - 1) as f32 * p).round() as usize;
+    sorted[i]
+}
+
+pub fn mean(samples: &[f32]) -> f32 {
+    samples.iter().sum::<f32>() / samples.len() as f32
+}
+
+/// Sample standard deviation (with Bessel's correction).
+pub fn std(samples: &[f32], mean: f32) -> f32 {
+    if samples.len() < 2 {
+        return f32::NAN;
+    }
+    let ss = samples.iter().map(|s| (s - mean) * (s - mean)).sum::<f32>();
+    (ss / (samples.len() - 1) as f32).sqrt()
+}
+
+/// The 97.5% quantile of Student's t distribution, for two sided 95%
+/// intervals. Above 30 degrees of freedom the normal quantile is close
+/// enough.
+pub fn t_critical_95(degrees_of_freedom: usize) -> f32 {
+    const TABLE: [f32; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+        2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+        2.052, 2.048, 2.045, 2.042,
+    ];
+    match degrees_of_freedom {
+        0 => f32::NAN,
+        d if d <= TABLE.len() => TABLE[d - 1],
+        _ => 1.96,
+    }
+}
+
+/// Result of [`welch_t_test`].
+#[derive(Debug, Clone, Copy)]
+pub struct TTest {
+    pub t: f32,
+    pub degrees_of_freedom: f32,
+    /// Two sided p-value.
+    pub p: f32,
+}
+
+/// Welch's t-test for a difference between the means of two samples that
+/// can have different variances, from their summaries.
+pub fn welch_t_test(a: (f32, f32, usize), b: (f32, f32, usize)) -> TTest {
+    let (mean_a, std_a, n_a) = a;
+    let (mean_b, std_b, n_b) = b;
+    let va = (std_a * std_a / n_a as f32) as f64;
+    let vb = (std_b * std_b / n_b as f32) as f64;
+    let t = (mean_a - mean_b) as f64 / (va + vb).sqrt();
+    let df = (va + vb).powi(2) / (va * va / (n_a as f64 - 1.) + vb * vb / (n_b as f64 - 1.));
+    let p = if t.is_finite() {
+        regularized_incomplete_beta(df / (df + t * t), df / 2., 0.5)
+    } else if va + vb == 0. && mean_a != mean_b {
+        0.
+    } else {
+        f64::NAN
+    };
+    TTest {
+        t: t as f32,
+        degrees_of_freedom: df as f32,
+        p: p as f32,
+    }
+}
+
+/// `I_x(a, b)`, evaluated with the continued fraction from Numerical
+/// Recipes.
+fn regularized_incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0. {
+        return 0.;
+    }
+    if x >= 1. {
+        return 1.;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1. - x).ln()).exp();
+    // The continued fraction converges quickly on this side only.
+    if x < (a + 1.) / (a + b + 2.) {
+        front * beta_continued_fraction(x, a, b) / a
+    } else {
+        1. - front * beta_continued_fraction(1. - x, b, a) / b
+    }
+}
+
+fn beta_continued_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let mut c = 1.;
+    let mut d = 1. - (a + b) * x / (a + 1.);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1. / d;
+    let mut h = d;
+    for m in 1..200 {
+        let m = m as f64;
+        let m2 = 2. * m;
+        for &aa in [
+            m * (b - m) * x / ((a + m2 - 1.) * (a + m2)),
+            -(a + m) * (a + b + m) * x / ((a + m2) * (a + m2 + 1.)),
+        ]
+        .iter()
+        {
+            d = 1. + aa * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1. + aa / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1. / d;
+            h *= d * c;
+        }
+        if (d * c - 1.).abs() < 1e-12 {
+            break;
+        }
+    }
+    h
+}
+
+/// Lanczos approximation of `ln(gamma(x))` for positive `x`.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut series = 1.000000000190015;
+    for (i, c) in COEFFICIENTS.iter().enumerate() {
+        series += c / (x + 1. + i as f64);
+    }
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Example 1 of the Wikipedia article on Welch's t-test.
+    #[test]
+    fn welch_t_test_matches_the_textbook_example() {
+        let a = [
+            27.5, 21.0, 19.0, 23.6, 17.0, 17.9, 16.9, 20.1, 21.9, 22.6, 23.1, 19.6, 19.0, 21.7,
+            21.4,
+        ];
+        let b = [
+            27.1, 22.0, 20.8, 23.4, 23.4, 23.5, 25.8, 22.0, 24.8, 20.2, 21.9, 22.1, 22.9, 20.5,
+            24.4,
+        ];
+        let a = summarize(&a, None);
+        let b = summarize(&b, None);
+        let test = welch_t_test((a.mean, a.std, a.n), (b.mean, b.std, b.n));
+
+        assert!((test.t - -2.4554).abs() < 1e-3, "{:?}", test);
+        assert!(
+            (test.degrees_of_freedom - 24.989).abs() < 1e-2,
+            "{:?}",
+            test
+        );
+        assert!((test.p - 0.02138).abs() < 1e-4, "{:?}", test);
+    }
+
+    #[test]
+    fn equal_means_are_not_significant() {
+        let test = welch_t_test((1., 0.5, 10), (1., 2., 20));
+        assert_eq!(test.t, 0.);
+        assert!((test.p - 1.).abs() < 1e-6, "{:?}", test);
+    }
+
+    #[test]
+    fn t_critical_95_for_small_degrees_of_freedom() {
+        assert!(t_critical_95(0).is_nan());
+        for &(df, t) in [
+            (1, 12.706),
+            (2, 4.303),
+            (5, 2.571),
+            (10, 2.228),
+            (30, 2.042),
+        ]
+        .iter()
+        {
+            assert_eq!(t_critical_95(df), t);
+        }
+        assert_eq!(t_critical_95(31), 1.96);
+    }
+
+    #[test]
+    fn nan_samples_are_rejected() {
+        let samples = [1., f32::NAN, 2., 3., f32::NAN];
+        for &outlier_iqr in [None, Some(1.5)].iter() {
+            let summary = summarize(&samples, outlier_iqr);
+            assert_eq!(summary.n, 3);
+            assert_eq!(summary.n_outliers, 2);
+            assert_eq!(summary.mean, 2.);
+            assert_eq!(summary.median, 2.);
+            assert_eq!(summary.max, 3.);
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! Benchmark suites, read from a TOML file like `bench.toml`. A suite lists
+//! the encodings to benchmark, each a shader with the vertex layout it
+//! reads, and the workloads, densities, resolutions, pipeline stages and
+//! decode placements to run all of them at. The benchmark runs the whole
+//! matrix in one process.
+//!
+//! ```toml
+//! n_iter = 200
+//! resolutions = [[1600, 1600]]
+//! densities = [0.8, 0.4]
+//! stages = ["full", "vertex", "fragment"]
+//! decode_placements = ["default", "per-vertex", "per-fragment", "hybrid"]
+//!
+//! [[encoding]]
+//! technique = "rotor"
+//!
+//! [[encoding]]
+//! name = "rotor (rotor16)"
+//! shader = "shaders/nm_rotor.glsl"
+//! vertex = "rotor16"
+//!
+//! [[workload]]
+//! name = "sphere perspective"
+//! mesh = "sphere"
+//! camera = "perspective"
+//! ```
+
+use crate::instancing;
+use crate::shaders::DecodePlacement;
+use crate::techniques::{self, Technique};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct Suite {
+    /// Measured frames per benchmark.
+    #[serde(default = "default_n_iter")]
+    pub n_iter: usize,
+    /// Frames drawn before measuring.
+    #[serde(default = "default_warmup")]
+    pub warmup: usize,
+    /// See [`crate::stats::summarize`], 0 keeps all frames.
+    #[serde(default = "default_outlier_iqr")]
+    pub outlier_iqr: f32,
+    /// Framebuffer width and height.
+    #[serde(default = "default_resolutions")]
+    pub resolutions: Vec<[usize; 2]>,
+    /// Number of mesh vertices per fragment.
+    #[serde(default = "default_densities")]
+    pub densities: Vec<f32>,
+    /// Replaces `densities` when given.
+    #[serde(default)]
+    pub density_sweep: Option<Sweep>,
+    /// A single orthographic grid when left out.
+    #[serde(default = "default_workloads", rename = "workload")]
+    pub workloads: Vec<Workload>,
+    /// Only the whole draw is timed when left out.
+    #[serde(default = "default_stages")]
+    pub stages: Vec<Stage>,
+    /// Every encoding is compiled with each of these, only with the
+    /// shader's own default when left out.
+    #[serde(default = "default_decode_placements")]
+    pub decode_placements: Vec<DecodePlacement>,
+    #[serde(rename = "encoding")]
+    pub encodings: Vec<Encoding>,
+}
+
+/// An encoding is either given by its shader and vertex layout, or taken
+/// from a registered technique (see `techniques`), in which case the
+/// fields that are given override the ones of the technique.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Encoding {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub technique: Option<String>,
+    #[serde(default)]
+    pub shader: String,
+    /// Name of the vertex layout, see `shader_bench`.
+    #[serde(default)]
+    pub vertex: String,
+    /// Defined in both shader stages.
+    #[serde(default)]
+    pub defines: Vec<String>,
+}
+
+impl Encoding {
+    pub fn from_technique(technique: &Technique) -> Self {
+        Self {
+            name: technique.name.to_string(),
+            technique: Some(technique.name.to_string()),
+            shader: technique.shader.to_string(),
+            vertex: technique.vertex.to_string(),
+            defines: technique.defines.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    /// Fills in the fields left out with those of the technique.
+    fn resolve(&mut self) {
+        if let Some(name) = &self.technique {
+            let technique = techniques::find(name)
+                .unwrap_or_else(|| panic!("Unknown technique {} in suite", name));
+            let defaults = Self::from_technique(technique);
+            if self.name.is_empty() {
+                self.name = defaults.name;
+            }
+            if self.shader.is_empty() {
+                self.shader = defaults.shader;
+            }
+            if self.vertex.is_empty() {
+                self.vertex = defaults.vertex;
+            }
+            let mut defines = defaults.defines;
+            defines.append(&mut self.defines);
+            self.defines = defines;
+        }
+        if self.name.is_empty() || self.shader.is_empty() || self.vertex.is_empty() {
+            panic!(
+                "Encoding {:?} needs a technique or a name, shader and vertex",
+                self
+            );
+        }
+    }
+}
+
+/// What is drawn for every encoding.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Workload {
+    pub name: String,
+    /// grid, sphere, saddle or a path to an obj file. The obj files have a
+    /// fixed density, the others are built for every density.
+    #[serde(default = "default_mesh")]
+    pub mesh: String,
+    #[serde(default)]
+    pub camera: Camera,
+    /// Copies of the mesh stacked towards the camera and drawn back to
+    /// front, so every fragment is shaded this many times.
+    #[serde(default = "one")]
+    pub layers: usize,
+    /// Instances per draw call, each with its own transform (see
+    /// `instancing`), at most `instancing::MAX_INSTANCES`. The instances
+    /// are turned a bit about the center of the mesh and mostly overlap, so
+    /// the vertex work is multiplied while most fragments fail the depth
+    /// test.
+    #[serde(default = "one")]
+    pub instances: usize,
+}
+
+impl Default for Workload {
+    fn default() -> Self {
+        Self {
+            name: "grid".to_string(),
+            mesh: default_mesh(),
+            camera: Camera::default(),
+            layers: 1,
+            instances: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Camera {
+    #[default]
+    Ortho,
+    Perspective,
+}
+
+/// What part of the pipeline a benchmark times.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stage {
+    /// The whole draw.
+    #[default]
+    Full,
+    /// The draw rasterized to a 1 by 1 viewport, so nearly all of the time
+    /// is spent in the vertex shader.
+    Vertex,
+    /// A full-screen quad, so the vertex shader only runs for the four
+    /// corners and the fragment shader gets the interpolants baked into
+    /// them.
+    Fragment,
+}
+
+impl Stage {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "full" => Stage::Full,
+            "vertex" => Stage::Vertex,
+            "fragment" => Stage::Fragment,
+            _ => panic!("Unknown stage {}, use full, vertex or fragment", name),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Full => "full",
+            Stage::Vertex => "vertex",
+            Stage::Fragment => "fragment",
+        }
+    }
+}
+
+/// `steps` densities from `min` to `max`, evenly spaced on a log scale.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Sweep {
+    pub min: f32,
+    pub max: f32,
+    pub steps: usize,
+}
+
+impl Sweep {
+    pub fn values(&self) -> Vec<f32> {
+        if self.steps < 2 {
+            return vec![self.min];
+        }
+        let ratio = (self.max / self.min).powf(1. / (self.steps - 1) as f32);
+        (0..self.steps)
+            .map(|i| self.min * ratio.powi(i as i32))
+            .collect()
+    }
+}
+
+fn default_n_iter() -> usize {
+    200
+}
+
+fn default_warmup() -> usize {
+    5
+}
+
+fn default_outlier_iqr() -> f32 {
+    1.5
+}
+
+fn default_resolutions() -> Vec<[usize; 2]> {
+    vec![[1600, 1600]]
+}
+
+fn default_densities() -> Vec<f32> {
+    vec![0.8, 0.4]
+}
+
+fn default_workloads() -> Vec<Workload> {
+    vec![Workload::default()]
+}
+
+fn default_stages() -> Vec<Stage> {
+    vec![Stage::Full]
+}
+
+fn default_decode_placements() -> Vec<DecodePlacement> {
+    vec![DecodePlacement::Default]
+}
+
+fn default_mesh() -> String {
+    "grid".to_string()
+}
+
+fn one() -> usize {
+    1
+}
+
+impl Suite {
+    pub fn from_path(path: impl AsRef<std::path::Path>) -> Self {
+        let source = std::fs::read_to_string(path).expect("Could not read suite file");
+        let mut suite: Suite = toml::from_str(&source).expect("Invalid suite file");
+        for encoding in suite.encodings.iter_mut() {
+            encoding.resolve();
+        }
+        for workload in suite.workloads.iter() {
+            if workload.instances > instancing::MAX_INSTANCES {
+                panic!(
+                    "Workload {} has {} instances, at most {} are supported",
+                    workload.name,
+                    workload.instances,
+                    instancing::MAX_INSTANCES
+                );
+            }
+        }
+        suite
+    }
+
+    /// The densities to run at, from the sweep when there is one.
+    pub fn densities(&self) -> Vec<f32> {
+        match self.density_sweep {
+            Some(sweep) => sweep.values(),
+            None => self.densities.clone(),
+        }
+    }
+}
+
+/// Identifies the rows of one benchmark process in the results, the start
+/// time in seconds followed by the process id.
+pub fn new_run_id() -> String {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    format!("{}-{}", seconds, std::process::id())
+}
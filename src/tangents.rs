@@ -0,0 +1,240 @@
+//! Tangent generation for meshes that come without tangents, like the
+//! ones loaded with `pgeom::obj`. Follows the MikkTSpace approach: per face
+//! tangents are derived from the uv derivatives, accumulated per vertex
+//! weighted by the corner angle, and vertices that are shared by faces with
+//! a different uv orientation (mirrored uv islands) are split, so that every
+//! vertex ends up with a single handedness.
+
+use crate::vertices::PosUVNormTang;
+
+/// Below this (doubled) signed uv area a face is considered degenerate. It
+/// does not contribute to the tangents of its vertices, and as the sign of
+/// its area is noise it does not split them either.
+const DEGENERATE_UV_AREA: f32 = 1e-12;
+
+/// Accumulated tangent frame of one vertex for one uv orientation.
+#[derive(Clone, Copy)]
+struct Accumulator {
+    tangent: glm::Vec3,
+    bitangent: glm::Vec3,
+}
+
+impl Accumulator {
+    fn new() -> Self {
+        Self {
+            tangent: glm::Vec3::zeros(),
+            bitangent: glm::Vec3::zeros(),
+        }
+    }
+}
+
+/// Generates tangents and handedness signs for the vertices of an indexed
+/// triangle mesh. Any existing tangents are overwritten. Vertices that lie
+/// on a mirror seam are duplicated and the faces are re-indexed, so the
+/// vertex list can grow.
+pub fn generate(vertices: &mut Vec<PosUVNormTang>, faces: &mut [[u32; 3]]) {
+    let n_vertices = vertices.len();
+    // One accumulator per orientation, index 0 for positive uv area and
+    // index 1 for negative (mirrored) uv area.
+    let mut accumulators = vec![[None::<Accumulator>; 2]; n_vertices];
+    let mut orientations = Vec::with_capacity(faces.len());
+
+    for face in faces.iter() {
+        let p: Vec<glm::Vec3> = face
+            .iter()
+            .map(|&i| vertices[i as usize].position.into())
+            .collect();
+        let uv: Vec<glm::Vec2> = face
+            .iter()
+            .map(|&i| vertices[i as usize].uv.into())
+            .collect();
+
+        let e1 = p[1] - p[0];
+        let e2 = p[2] - p[0];
+        let duv1 = uv[1] - uv[0];
+        let duv2 = uv[2] - uv[0];
+        let signed_area = duv1.x * duv2.y - duv2.x * duv1.y;
+        if signed_area.abs() < DEGENERATE_UV_AREA {
+            orientations.push(0);
+            continue;
+        }
+        let orientation = if signed_area < 0. { 1 } else { 0 };
+        orientations.push(orientation);
+        let r = 1. / signed_area;
+        let face_tangent = (e1 * duv2.y - e2 * duv1.y) * r;
+        let face_bitangent = (e2 * duv1.x - e1 * duv2.x) * r;
+
+        for corner in 0..3 {
+            let i = face[corner] as usize;
+            let to_next = p[(corner + 1) % 3] - p[corner];
+            let to_prev = p[(corner + 2) % 3] - p[corner];
+            let angle = glm::angle(&to_next, &to_prev);
+            if !angle.is_finite() {
+                continue;
+            }
+
+            // Project on the tangent plane of the vertex before accumulating,
+            // so faces that are tilted relative to the vertex normal do not
+            // pull the tangent out of plane.
+            let normal: glm::Vec3 = vertices[i].normal.into();
+            let normal = normal.normalize();
+            let tangent = project_on_plane(&face_tangent, &normal);
+            let bitangent = project_on_plane(&face_bitangent, &normal);
+
+            let acc = accumulators[i][orientation].get_or_insert_with(Accumulator::new);
+            acc.tangent += tangent * angle;
+            acc.bitangent += bitangent * angle;
+        }
+    }
+
+    // Vertices of only degenerate faces still get a (fallback) tangent.
+    for acc in accumulators.iter_mut() {
+        if let [None, None] = acc {
+            acc[0] = Some(Accumulator::new());
+        }
+    }
+
+    // Split vertices that are used with both orientations, the mirrored
+    // copy is appended to the vertex list.
+    let mut mirrored_index = vec![None; n_vertices];
+    for i in 0..n_vertices {
+        if let [Some(_), Some(_)] = accumulators[i] {
+            let v = &vertices[i];
+            mirrored_index[i] = Some(vertices.len() as u32);
+            vertices.push(PosUVNormTang {
+                position: v.position,
+                uv: v.uv,
+                normal: v.normal,
+                tangent: v.tangent,
+                handedness: v.handedness,
+            });
+        }
+    }
+    for (face, &orientation) in faces.iter_mut().zip(orientations.iter()) {
+        if orientation == 0 {
+            continue;
+        }
+        for i in face.iter_mut() {
+            if let Some(mirrored) = mirrored_index[*i as usize] {
+                *i = mirrored;
+            }
+        }
+    }
+
+    for i in 0..n_vertices {
+        for (orientation, acc) in accumulators[i].iter().enumerate() {
+            let acc = match acc {
+                Some(acc) => acc,
+                None => continue,
+            };
+            let index = if orientation == 1 {
+                mirrored_index[i].map(|m| m as usize).unwrap_or(i)
+            } else {
+                i
+            };
+            let v = &mut vertices[index];
+            let normal: glm::Vec3 = v.normal.into();
+            let normal = normal.normalize();
+
+            let tangent = project_on_plane(&acc.tangent, &normal);
+            let tangent = if tangent.norm() > f32::EPSILON {
+                tangent.normalize()
+            } else {
+                any_perpendicular(&normal)
+            };
+            let handedness = if glm::dot(&glm::cross(&normal, &tangent), &acc.bitangent) < 0. {
+                -1.
+            } else {
+                1.
+            };
+
+            v.tangent = tangent.into();
+            v.handedness = handedness;
+        }
+    }
+}
+
+fn project_on_plane(v: &glm::Vec3, normal: &glm::Vec3) -> glm::Vec3 {
+    v - normal * glm::dot(normal, v)
+}
+
+/// Tangent used for vertices whose faces all have degenerate uv's.
+fn any_perpendicular(normal: &glm::Vec3) -> glm::Vec3 {
+    let helper = if normal.x.abs() < 0.9 {
+        glm::Vec3::x()
+    } else {
+        glm::Vec3::y()
+    };
+    glm::cross(&helper, normal).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(position: [f32; 3], uv: [f32; 2]) -> PosUVNormTang {
+        PosUVNormTang {
+            position,
+            uv,
+            normal: [0., 0., 1.],
+            tangent: [0., 0., 0.],
+            handedness: 1.,
+        }
+    }
+
+    fn assert_unit_tangents(vertices: &[PosUVNormTang]) {
+        for v in vertices.iter() {
+            let tangent: glm::Vec3 = v.tangent.into();
+            assert!((tangent.norm() - 1.).abs() < 1e-5, "{:?}", v);
+            assert!(v.handedness == 1. || v.handedness == -1., "{:?}", v);
+        }
+    }
+
+    /// The second triangle has its uv's mirrored, so the two vertices on
+    /// the shared edge are split.
+    #[test]
+    fn mirrored_quad_splits_the_shared_vertices() {
+        let mut vertices = vec![
+            vertex([0., 0., 0.], [0., 0.]),
+            vertex([1., 0., 0.], [1., 0.]),
+            vertex([1., 1., 0.], [1., 1.]),
+            vertex([0., 1., 0.], [2., 0.]),
+        ];
+        let mut faces = [[0, 1, 2], [0, 2, 3]];
+        generate(&mut vertices, &mut faces);
+
+        assert_eq!(vertices.len(), 6);
+        assert_eq!(faces[0], [0, 1, 2]);
+        assert_eq!(faces[1], [4, 5, 3]);
+        assert_unit_tangents(&vertices);
+        for &i in faces[0].iter() {
+            assert_eq!(vertices[i as usize].handedness, 1.);
+        }
+        for &i in faces[1].iter() {
+            assert_eq!(vertices[i as usize].handedness, -1.);
+        }
+    }
+
+    /// The last triangle has a tiny negative uv area, which is noise and
+    /// must neither split its vertices nor produce NaNs.
+    #[test]
+    fn degenerate_face_does_not_split() {
+        let mut vertices = vec![
+            vertex([0., 0., 0.], [0., 0.]),
+            vertex([1., 0., 0.], [1., 0.]),
+            vertex([1., 1., 0.], [1., 1.]),
+            vertex([0., 1., 0.], [0., 1.]),
+            vertex([-1., 0.5, 0.], [-1e-13, 0.5]),
+        ];
+        let mut faces = [[0, 1, 2], [0, 2, 3], [0, 4, 3]];
+        generate(&mut vertices, &mut faces);
+
+        assert_eq!(vertices.len(), 5);
+        assert_eq!(faces, [[0, 1, 2], [0, 2, 3], [0, 4, 3]]);
+        assert_unit_tangents(&vertices);
+        for v in vertices[..4].iter() {
+            assert!((v.tangent[0] - 1.).abs() < 1e-5, "{:?}", v);
+            assert_eq!(v.handedness, 1.);
+        }
+    }
+}
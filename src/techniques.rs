@@ -0,0 +1,217 @@
+//! The registry of shading techniques. A technique is a shader with the
+//! defines it is compiled with, the vertex layout it reads and the
+//! textures it samples. The shading list of the app and the encodings that
+//! `shader_bench` knows by name are generated from [`TECHNIQUES`], so a new
+//! encoding only has to be registered here.
+
+/// A texture that a technique samples. The app decides which texture slot
+/// it is bound to.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Texture {
+    Albedo,
+    NormalMap,
+    /// Roughness in the green channel, as in glTF.
+    Roughness,
+}
+
+impl Texture {
+    /// As written in scene files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Texture::Albedo => "albedo",
+            Texture::NormalMap => "normal-map",
+            Texture::Roughness => "roughness",
+        }
+    }
+
+    /// The sampler uniform in the shaders.
+    pub fn uniform(&self) -> &'static str {
+        match self {
+            Texture::Albedo => "uAlbedoMap",
+            Texture::NormalMap => "uNormalMap",
+            Texture::Roughness => "uRoughnessMap",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Technique {
+    /// Used in suites and results.
+    pub name: &'static str,
+    /// Shown in the app.
+    pub label: &'static str,
+    pub shader: &'static str,
+    /// Defined in both shader stages.
+    pub defines: &'static [&'static str],
+    /// The vertex layout `shader_bench` draws it with, by the name used in
+    /// suites. The app draws everything with `vertices::All`.
+    pub vertex: &'static str,
+    pub textures: &'static [Texture],
+    /// A tangent frame encoding, benchmarked by `--techniques all`.
+    pub encoding: bool,
+    /// Listed in the app, which cannot draw the skinned layouts.
+    pub app: bool,
+}
+
+pub const TECHNIQUES: &[Technique] = &[
+    Technique {
+        name: "flat",
+        label: "Flat Phong Shading",
+        shader: "shaders/flat.glsl",
+        defines: &[],
+        vertex: "normtang",
+        textures: &[],
+        encoding: false,
+        app: true,
+    },
+    Technique {
+        name: "albedo",
+        label: "Albedo Mapping",
+        shader: "shaders/tex.glsl",
+        defines: &[],
+        vertex: "normtang",
+        textures: &[Texture::Albedo, Texture::Roughness],
+        encoding: false,
+        app: true,
+    },
+    Technique {
+        name: "matrix",
+        label: "Normal Mapping With Matrix",
+        shader: "shaders/nm_matrix.glsl",
+        defines: &[],
+        vertex: "matrix",
+        textures: &[Texture::NormalMap],
+        encoding: true,
+        app: true,
+    },
+    Technique {
+        name: "normal and albedo",
+        label: "Normal And Albedo Mapping",
+        shader: "shaders/nm_tex.glsl",
+        defines: &[],
+        vertex: "normtang",
+        textures: &[Texture::NormalMap, Texture::Albedo, Texture::Roughness],
+        encoding: false,
+        app: true,
+    },
+    Technique {
+        name: "rotor",
+        label: "Normal Mapping With Rotor",
+        shader: "shaders/nm_rotor.glsl",
+        defines: &[],
+        vertex: "rotor",
+        textures: &[Texture::NormalMap],
+        encoding: true,
+        app: true,
+    },
+    Technique {
+        name: "motor",
+        label: "Normal Mapping With Motor",
+        shader: "shaders/nm_motor.glsl",
+        defines: &[],
+        vertex: "motor",
+        textures: &[Texture::NormalMap],
+        encoding: true,
+        app: true,
+    },
+    Technique {
+        name: "outer exponent motor",
+        label: "Normal Mapping With Outer Log Motor",
+        shader: "shaders/nm_outer_log_motor.glsl",
+        defines: &[],
+        vertex: "outermotor",
+        textures: &[Texture::NormalMap],
+        encoding: true,
+        app: true,
+    },
+    Technique {
+        name: "outer exponent rotor",
+        label: "Normal Mapping With Outer Log Rotor",
+        shader: "shaders/nm_outer_log_rotor.glsl",
+        defines: &[],
+        vertex: "outerrotor",
+        textures: &[Texture::NormalMap],
+        encoding: true,
+        app: true,
+    },
+    Technique {
+        name: "qtangent rotor",
+        label: "Normal Mapping With QTangent",
+        shader: "shaders/nm_qtang.glsl",
+        defines: &[],
+        vertex: "qrotor",
+        textures: &[Texture::NormalMap],
+        encoding: true,
+        app: true,
+    },
+    Technique {
+        name: "normal and tangent",
+        label: "Normal Mapping With Tang and BiTang",
+        shader: "shaders/nm_bitang.glsl",
+        defines: &[],
+        vertex: "normtang",
+        textures: &[Texture::NormalMap],
+        encoding: true,
+        app: true,
+    },
+    Technique {
+        name: "cayley motor",
+        label: "With Cayley Motor",
+        shader: "shaders/nm_cayley_motor.glsl",
+        defines: &[],
+        vertex: "cayleymotor",
+        textures: &[Texture::NormalMap],
+        encoding: true,
+        app: true,
+    },
+    Technique {
+        name: "cayley rotor",
+        label: "With Cayley Rotor",
+        shader: "shaders/nm_cayley_rotor.glsl",
+        defines: &[],
+        vertex: "cayleyrotor",
+        textures: &[Texture::NormalMap],
+        encoding: true,
+        app: true,
+    },
+    Technique {
+        name: "exponent motor",
+        label: "Normal Mapping With Log Motor",
+        shader: "shaders/nm_log_motor.glsl",
+        defines: &[],
+        vertex: "logmotor",
+        textures: &[Texture::NormalMap],
+        encoding: true,
+        app: true,
+    },
+    Technique {
+        name: "dq skinning (motor)",
+        label: "Dual Quaternion Skinning",
+        shader: "shaders/nm_skin_motor.glsl",
+        defines: &[],
+        vertex: "skinnedmotor",
+        textures: &[Texture::NormalMap],
+        encoding: false,
+        app: false,
+    },
+    Technique {
+        name: "linear blend skinning (matrix)",
+        label: "Linear Blend Skinning",
+        shader: "shaders/nm_skin_matrix.glsl",
+        defines: &[],
+        vertex: "skinnedmatrix",
+        textures: &[Texture::NormalMap],
+        encoding: false,
+        app: false,
+    },
+];
+
+/// The techniques the app lists and draws with `vertices::All`.
+pub fn in_app() -> impl Iterator<Item = &'static Technique> {
+    TECHNIQUES.iter().filter(|t| t.app)
+}
+
+pub fn find(name: &str) -> Option<&'static Technique> {
+    TECHNIQUES.iter().find(|t| t.name == name)
+}
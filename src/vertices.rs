@@ -1,62 +1,54 @@
+use crate::{reference, skinning};
 use pgl::vao::HasVertexAttributes;
 use pgl::GlslDType;
 
-#[repr(C)]
-#[derive(Debug)]
-pub struct All {
-    pub position: [f32; 3],
-    pub uv: [f32; 2],
-    pub normal: [f32; 3],
-    pub tangent: [f32; 3],
-    pub bitangent: [f32; 3],
-    pub rotor: [f32; 4],      // tangent to world space rotor (quaternion)
-    pub motor: [[f32; 4]; 2], // tangent to world space motor (dual quaternion)
-    pub outer_log_motor: [[f32; 3]; 2],
-    pub outer_log_rotor: [f32; 3],
-    pub qtang: [f32; 3],
-    pub cayley_motor: [[f32; 3]; 2],
-    pub cayley_rotor: [f32; 3],
-}
-impl HasVertexAttributes for All {
-    fn attributes() -> Vec<GlslDType> {
-        vec![
-            GlslDType::Vec3, // pos
-            GlslDType::Vec2, // uv
-            GlslDType::Vec3, // normal
-            GlslDType::Vec3, // tangent
-            GlslDType::Vec3, // bitangent
-            GlslDType::Vec4, // rotor
-            GlslDType::Vec4, // motor
-            GlslDType::Vec4,
-            GlslDType::Vec3, // outer log bivec
-            GlslDType::Vec3,
-            GlslDType::Vec3, // outer log rotor bivec
-            GlslDType::Vec3, // qtangent
-            GlslDType::Vec3, // cayley motor
-            GlslDType::Vec3,
-            GlslDType::Vec3, // cayley rotor
-        ]
+// The layouts that have an attribute block in the shaders are declared
+// with `vertex_layout!`, see `layout`. The string after the name is the
+// define that selects the block. They are built from `All`, so after
+// `antipodal::align` they share its rotor signs.
+
+vertex_layout! {
+    #[derive(Debug, Clone)]
+    pub struct All: "DEFAULT_ATTRIBUTES" {
+        pub position: [f32; 3] => [Vec3 aPos],
+        pub uv: [f32; 2] => [Vec2 aUV],
+        pub normal: [f32; 3] => [Vec3 aNormal],
+        pub tangent: [f32; 3] => [Vec3 aTangent],
+        pub bitangent: [f32; 3] => [Vec3 aBiTangent],
+        /// tangent to world space rotor (quaternion)
+        pub rotor: [f32; 4] => [Vec4 aTangentToModelSpaceRotor],
+        /// tangent to world space motor (dual quaternion)
+        pub motor: [[f32; 4]; 2] => [
+            Vec4 aTangentToModelSpaceMotor1,
+            Vec4 aTangentToModelSpaceMotor2
+        ],
+        pub outer_log_motor: [[f32; 3]; 2] => [
+            Vec3 aTangentToModelSpaceOuterEBivector,
+            Vec3 aTangentToModelSpaceOuterVBivector
+        ],
+        pub log_motor: [[f32; 3]; 2] => [
+            Vec3 aTangentToModelSpaceLogEBivector,
+            Vec3 aTangentToModelSpaceLogVBivector
+        ],
+        pub qtang: [f32; 3] => [Vec3 aTangentToModelSpaceQTang],
+        pub cayley_motor: [[f32; 3]; 2] => [
+            Vec3 aTangentToModelSpaceCayleyEBivector,
+            Vec3 aTangentToModelSpaceCayleyVBivector
+        ],
+        /// sign of the bitangent, the rotors always describe a right handed frame
+        pub handedness: f32 => [Float aTangentHandedness],
     }
+    // The rotor parameters equal the euclidean part of the motor ones, they
+    // are aliased to stay within the 16 attribute locations GL guarantees.
+    "#define aTangentToModelSpaceOuterRotor aTangentToModelSpaceOuterEBivector\n\
+     #define aTangentToModelSpaceCayleyRotor aTangentToModelSpaceCayleyEBivector\n"
 }
 impl From<PosUVNormTang> for All {
     fn from(v: PosUVNormTang) -> Self {
-        let tangent: glm::Vec3 = v.tangent.into();
-        let normal: glm::Vec3 = v.normal.into();
-        let bitangent = glm::cross(&normal, &tangent);
-
-        let rotor = ppga::Rotor::from_base(
-            &tangent.normalize().into(),
-            &bitangent.normalize().into(),
-            &normal.normalize().into(),
-        )
-        .normalize();
-
-        let translator = ppga::Translator::new(&v.position);
-        let motor = translator.mul_rotor(&rotor);
-        let motor = motor.normalize();
+        let rotor = tangent_to_model_rotor(&v);
+        let motor = tangent_to_model_motor(&v);
 
         let outer_log_motor = motor.outer_ln();
-        let outer_log_rotor = rotor.outer_ln();
 
         // let m_ = log_motor.exp();
         // if !m_.is_similar_to(0.001, &motor) {
@@ -85,324 +77,337 @@ impl From<PosUVNormTang> for All {
             uv: v.uv,
             normal: v.normal,
             tangent: v.tangent,
-            bitangent: bitangent.into(),
+            bitangent: signed_bitangent(&v),
             motor: motor.into_klein(),
             rotor: rotor.into(),
             outer_log_motor: outer_log_motor.into(),
-            outer_log_rotor: outer_log_rotor.e_bivector,
+            log_motor: reference::ln_motor(motor.into_klein()),
             qtang: rotor.qtangent_ln(),
             cayley_motor: motor.cayley_ln().into(),
-            cayley_rotor: ppga::Motor::from(&rotor).cayley_ln().e_bivector,
+            handedness: v.handedness,
         }
     }
 }
-
-#[repr(C)]
-#[derive(Debug)]
-pub struct PosUVNormTang {
-    pub position: [f32; 3],
-    pub uv: [f32; 2],
-    pub normal: [f32; 3],
-    pub tangent: [f32; 3],
-}
-impl HasVertexAttributes for PosUVNormTang {
-    fn attributes() -> Vec<GlslDType> {
-        vec![
-            GlslDType::Vec3,
-            GlslDType::Vec2,
-            GlslDType::Vec3,
-            GlslDType::Vec3,
-        ]
+impl From<&All> for All {
+    fn from(v: &All) -> Self {
+        v.clone()
     }
 }
-#[repr(C)]
-#[derive(Debug)]
-pub struct Matrix {
-    pub position: [f32; 3],
-    pub uv: [f32; 2],
-    pub normal: [f32; 3],
-    pub tangent: [f32; 3],
-    pub bitangent: [f32; 3],
-}
-impl HasVertexAttributes for Matrix {
-    fn attributes() -> Vec<GlslDType> {
-        vec![
-            GlslDType::Vec3,
-            GlslDType::Vec2,
-            GlslDType::Vec3,
-            GlslDType::Vec3,
-            GlslDType::Vec3,
-        ]
+
+vertex_layout! {
+    #[derive(Debug, Clone)]
+    pub struct PosUVNormTang: "NORMTANG_ATTRIBUTES" {
+        pub position: [f32; 3] => [Vec3 aPos],
+        pub uv: [f32; 2] => [Vec2 aUV],
+        pub normal: [f32; 3] => [Vec3 aNormal],
+        pub tangent: [f32; 3] => [Vec3 aTangent],
+        /// sign of the bitangent relative to cross(normal, tangent)
+        pub handedness: f32 => [Float aTangentHandedness],
     }
 }
-impl From<PosUVNormTang> for Matrix {
-    fn from(v: PosUVNormTang) -> Self {
-        let tangent: glm::Vec3 = v.tangent.into();
-        let normal: glm::Vec3 = v.normal.into();
-        let bitangent = glm::cross(&normal, &tangent);
-
+impl From<&All> for PosUVNormTang {
+    fn from(v: &All) -> Self {
         Self {
             position: v.position,
             uv: v.uv,
-            tangent: tangent.into(),
-            normal: normal.into(),
-            bitangent: bitangent.into(),
+            normal: v.normal,
+            tangent: v.tangent,
+            handedness: v.handedness,
         }
     }
 }
 
-#[repr(C)]
-#[derive(Debug)]
-pub struct Rotor {
-    pub position: [f32; 3],
-    pub uv: [f32; 2],
-    pub rotor: [f32; 4], // tangent to world space rotor (quaternion)
-}
-impl HasVertexAttributes for Rotor {
-    fn attributes() -> Vec<GlslDType> {
-        vec![GlslDType::Vec3, GlslDType::Vec2, GlslDType::Vec4]
+vertex_layout! {
+    #[derive(Debug)]
+    pub struct Matrix: "MATRIX_ATTRIBUTES" {
+        pub position: [f32; 3] => [Vec3 aPos],
+        pub uv: [f32; 2] => [Vec2 aUV],
+        pub normal: [f32; 3] => [Vec3 aNormal],
+        pub tangent: [f32; 3] => [Vec3 aTangent],
+        pub bitangent: [f32; 3] => [Vec3 aBiTangent],
     }
 }
-impl From<PosUVNormTang> for Rotor {
-    fn from(v: PosUVNormTang) -> Self {
-        let tangent: glm::Vec3 = v.tangent.into();
-        let normal: glm::Vec3 = v.normal.into();
-        let bitangent = glm::cross(&normal, &tangent);
-
-        let rotor = ppga::Rotor::from_base(
-            &tangent.normalize().into(),
-            &bitangent.normalize().into(),
-            &normal.normalize().into(),
-        )
-        .normalize()
-        .into();
-
+impl From<&All> for Matrix {
+    fn from(v: &All) -> Self {
         Self {
             position: v.position,
             uv: v.uv,
-            rotor,
+            tangent: v.tangent,
+            normal: v.normal,
+            bitangent: v.bitangent,
         }
     }
 }
-#[repr(C)]
-#[derive(Debug)]
-pub struct Motor {
-    pub position: [f32; 3],
-    pub uv: [f32; 2],
-    pub motor: [[f32; 4]; 2],
-}
-impl HasVertexAttributes for Motor {
-    fn attributes() -> Vec<GlslDType> {
-        vec![
-            GlslDType::Vec3,
-            GlslDType::Vec2,
-            GlslDType::Vec4,
-            GlslDType::Vec4,
-        ]
+impl_from_pos_uv_norm_tang!(Matrix);
+
+vertex_layout! {
+    #[derive(Debug)]
+    pub struct Rotor: "ROTOR_ATTRIBUTES" {
+        pub position: [f32; 3] => [Vec3 aPos],
+        pub uv: [f32; 2] => [Vec2 aUV],
+        /// tangent to world space rotor (quaternion)
+        pub rotor: [f32; 4] => [Vec4 aTangentToModelSpaceRotor],
+        pub handedness: f32 => [Float aTangentHandedness],
     }
 }
-impl From<PosUVNormTang> for Motor {
-    fn from(v: PosUVNormTang) -> Self {
-        let tangent: glm::Vec3 = v.tangent.into();
-        let normal: glm::Vec3 = v.normal.into();
-        let bitangent = glm::cross(&normal, &tangent);
-
-        let rotor = ppga::Rotor::from_base(
-            &tangent.normalize().into(),
-            &bitangent.normalize().into(),
-            &normal.normalize().into(),
-        )
-        .normalize()
-        .into();
-
-        let t = ppga::Translator::new(&v.position);
-        let m = t.mul_rotor(&rotor).normalize();
-
+impl From<&All> for Rotor {
+    fn from(v: &All) -> Self {
         Self {
             position: v.position,
             uv: v.uv,
-            motor: m.into_klein(),
+            rotor: v.rotor,
+            handedness: v.handedness,
         }
     }
 }
-#[repr(C)]
-#[derive(Debug)]
-pub struct QRotor {
-    pub position: [f32; 3],
-    pub uv: [f32; 2],
-    pub outer_rotor: [f32; 3],
+impl_from_pos_uv_norm_tang!(Rotor);
+
+vertex_layout! {
+    #[derive(Debug)]
+    pub struct Motor: "MOTOR_ATTRIBUTES" {
+        pub position: [f32; 3] => [Vec3 aPos],
+        pub uv: [f32; 2] => [Vec2 aUV],
+        pub motor: [[f32; 4]; 2] => [
+            Vec4 aTangentToModelSpaceMotor1,
+            Vec4 aTangentToModelSpaceMotor2
+        ],
+        pub handedness: f32 => [Float aTangentHandedness],
+    }
 }
-impl HasVertexAttributes for QRotor {
-    fn attributes() -> Vec<GlslDType> {
-        vec![GlslDType::Vec3, GlslDType::Vec2, GlslDType::Vec3]
+impl From<&All> for Motor {
+    fn from(v: &All) -> Self {
+        Self {
+            position: v.position,
+            uv: v.uv,
+            motor: v.motor,
+            handedness: v.handedness,
+        }
     }
 }
-impl From<PosUVNormTang> for QRotor {
-    fn from(v: PosUVNormTang) -> Self {
-        let tangent: glm::Vec3 = v.tangent.into();
-        let normal: glm::Vec3 = v.normal.into();
-        let bitangent = glm::cross(&normal, &tangent);
-
-        let rotor = ppga::Rotor::from_base(
-            &tangent.normalize().into(),
-            &bitangent.normalize().into(),
-            &normal.normalize().into(),
-        )
-        .normalize();
+impl_from_pos_uv_norm_tang!(Motor);
 
+vertex_layout! {
+    #[derive(Debug)]
+    pub struct QRotor: "QROTOR_ATTRIBUTES" {
+        pub position: [f32; 3] => [Vec3 aPos],
+        pub uv: [f32; 2] => [Vec2 aUV],
+        pub outer_rotor: [f32; 3] => [Vec3 aTangentToModelSpaceQTang],
+        pub handedness: f32 => [Float aTangentHandedness],
+    }
+}
+impl From<&All> for QRotor {
+    fn from(v: &All) -> Self {
         Self {
             position: v.position,
             uv: v.uv,
-            outer_rotor: rotor.qtangent_ln(),
+            outer_rotor: v.qtang,
+            handedness: v.handedness,
         }
     }
 }
-#[repr(C)]
-#[derive(Debug)]
-pub struct CayleyRotor {
-    pub position: [f32; 3],
-    pub uv: [f32; 2],
-    pub cayley_rotor: [f32; 3],
+impl_from_pos_uv_norm_tang!(QRotor);
+
+vertex_layout! {
+    #[derive(Debug)]
+    pub struct CayleyRotor: "CAYLEY_ROTOR_ATTRIBUTES" {
+        pub position: [f32; 3] => [Vec3 aPos],
+        pub uv: [f32; 2] => [Vec2 aUV],
+        pub cayley_rotor: [f32; 3] => [Vec3 aTangentToModelSpaceCayleyRotor],
+        pub handedness: f32 => [Float aTangentHandedness],
+    }
 }
-impl HasVertexAttributes for CayleyRotor {
-    fn attributes() -> Vec<GlslDType> {
-        vec![GlslDType::Vec3, GlslDType::Vec2, GlslDType::Vec3]
+impl From<&All> for CayleyRotor {
+    fn from(v: &All) -> Self {
+        Self {
+            position: v.position,
+            uv: v.uv,
+            cayley_rotor: v.cayley_motor[0],
+            handedness: v.handedness,
+        }
     }
 }
-impl From<PosUVNormTang> for CayleyRotor {
-    fn from(v: PosUVNormTang) -> Self {
-        let tangent: glm::Vec3 = v.tangent.into();
-        let normal: glm::Vec3 = v.normal.into();
-        let bitangent = glm::cross(&normal, &tangent);
-
-        let rotor = ppga::Rotor::from_base(
-            &tangent.normalize().into(),
-            &bitangent.normalize().into(),
-            &normal.normalize().into(),
-        )
-        .normalize();
+impl_from_pos_uv_norm_tang!(CayleyRotor);
 
+vertex_layout! {
+    #[derive(Debug)]
+    pub struct OuterRotor: "OUTER_ROTOR_ATTRIBUTES" {
+        pub position: [f32; 3] => [Vec3 aPos],
+        pub uv: [f32; 2] => [Vec2 aUV],
+        pub outer_rotor: [f32; 3] => [Vec3 aTangentToModelSpaceOuterRotor],
+        pub handedness: f32 => [Float aTangentHandedness],
+    }
+}
+impl From<&All> for OuterRotor {
+    fn from(v: &All) -> Self {
         Self {
             position: v.position,
             uv: v.uv,
-            cayley_rotor: ppga::Motor::from(&rotor).cayley_ln().e_bivector,
+            outer_rotor: v.outer_log_motor[0],
+            handedness: v.handedness,
         }
     }
 }
-#[repr(C)]
-#[derive(Debug)]
-pub struct OuterRotor {
-    pub position: [f32; 3],
-    pub uv: [f32; 2],
-    pub outer_rotor: [f32; 3],
+impl_from_pos_uv_norm_tang!(OuterRotor);
+
+vertex_layout! {
+    #[derive(Debug)]
+    pub struct CayleyMotor: "CAYLEY_MOTOR_ATTRIBUTES" {
+        pub position: [f32; 3] => [Vec3 aPos],
+        pub uv: [f32; 2] => [Vec2 aUV],
+        pub cayley_motor: [[f32; 3]; 2] => [
+            Vec3 aTangentToModelSpaceCayleyEBivector,
+            Vec3 aTangentToModelSpaceCayleyVBivector
+        ],
+        pub handedness: f32 => [Float aTangentHandedness],
+    }
 }
-impl HasVertexAttributes for OuterRotor {
-    fn attributes() -> Vec<GlslDType> {
-        vec![GlslDType::Vec3, GlslDType::Vec2, GlslDType::Vec3]
+impl From<&All> for CayleyMotor {
+    fn from(v: &All) -> Self {
+        Self {
+            position: v.position,
+            uv: v.uv,
+            cayley_motor: v.cayley_motor,
+            handedness: v.handedness,
+        }
     }
 }
-impl From<PosUVNormTang> for OuterRotor {
-    fn from(v: PosUVNormTang) -> Self {
-        let tangent: glm::Vec3 = v.tangent.into();
-        let normal: glm::Vec3 = v.normal.into();
-        let bitangent = glm::cross(&normal, &tangent);
-
-        let rotor = ppga::Rotor::from_base(
-            &tangent.normalize().into(),
-            &bitangent.normalize().into(),
-            &normal.normalize().into(),
-        )
-        .normalize();
+impl_from_pos_uv_norm_tang!(CayleyMotor);
 
+vertex_layout! {
+    #[derive(Debug)]
+    pub struct OuterMotor: "OUTER_MOTOR_ATTRIBUTES" {
+        pub position: [f32; 3] => [Vec3 aPos],
+        pub uv: [f32; 2] => [Vec2 aUV],
+        pub outer_motor: [[f32; 3]; 2] => [
+            Vec3 aTangentToModelSpaceOuterEBivector,
+            Vec3 aTangentToModelSpaceOuterVBivector
+        ],
+        pub handedness: f32 => [Float aTangentHandedness],
+    }
+}
+impl From<&All> for OuterMotor {
+    fn from(v: &All) -> Self {
         Self {
             position: v.position,
             uv: v.uv,
-            outer_rotor: rotor.outer_ln().e_bivector,
+            outer_motor: v.outer_log_motor,
+            handedness: v.handedness,
         }
     }
 }
-#[repr(C)]
-#[derive(Debug)]
-pub struct CayleyMotor {
-    pub position: [f32; 3],
-    pub uv: [f32; 2],
-    pub cayley_motor: [[f32; 3]; 2],
+impl_from_pos_uv_norm_tang!(OuterMotor);
+
+vertex_layout! {
+    #[derive(Debug)]
+    pub struct LogMotor: "LOG_MOTOR_ATTRIBUTES" {
+        pub position: [f32; 3] => [Vec3 aPos],
+        pub uv: [f32; 2] => [Vec2 aUV],
+        pub log_motor: [[f32; 3]; 2] => [
+            Vec3 aTangentToModelSpaceLogEBivector,
+            Vec3 aTangentToModelSpaceLogVBivector
+        ],
+        pub handedness: f32 => [Float aTangentHandedness],
+    }
 }
-impl HasVertexAttributes for CayleyMotor {
-    fn attributes() -> Vec<GlslDType> {
-        vec![
-            GlslDType::Vec3,
-            GlslDType::Vec2,
-            GlslDType::Vec3,
-            GlslDType::Vec3,
-        ]
+impl From<&All> for LogMotor {
+    fn from(v: &All) -> Self {
+        Self {
+            position: v.position,
+            uv: v.uv,
+            log_motor: v.log_motor,
+            handedness: v.handedness,
+        }
     }
 }
-impl From<PosUVNormTang> for CayleyMotor {
-    fn from(v: PosUVNormTang) -> Self {
-        let tangent: glm::Vec3 = v.tangent.into();
-        let normal: glm::Vec3 = v.normal.into();
-        let bitangent = glm::cross(&normal, &tangent);
-
-        let rotor = ppga::Rotor::from_base(
-            &tangent.normalize().into(),
-            &bitangent.normalize().into(),
-            &normal.normalize().into(),
-        )
-        .normalize()
-        .into();
+impl_from_pos_uv_norm_tang!(LogMotor);
 
-        let t = ppga::Translator::new(&v.position);
-        let m = t.mul_rotor(&rotor).normalize();
+// The skinned layouts are built for `skinning::Skeleton::chain`, with the
+// influences of `skinning::Influences::chain`.
 
+vertex_layout! {
+    #[derive(Debug)]
+    pub struct SkinnedMotor: "SKINNED_MOTOR_ATTRIBUTES" {
+        pub position: [f32; 3] => [Vec3 aPos],
+        pub uv: [f32; 2] => [Vec2 aUV],
+        pub motor: [[f32; 4]; 2] => [
+            Vec4 aTangentToModelSpaceMotor1,
+            Vec4 aTangentToModelSpaceMotor2
+        ],
+        pub handedness: f32 => [Float aTangentHandedness],
+        pub joints: [f32; 4] => [Vec4 aJoints],
+        pub weights: [f32; 4] => [Vec4 aWeights],
+    }
+}
+impl From<&All> for SkinnedMotor {
+    fn from(v: &All) -> Self {
+        let influences = skinning::Influences::chain(v.position);
         Self {
             position: v.position,
             uv: v.uv,
-            cayley_motor: m.cayley_ln().into(),
+            motor: v.motor,
+            handedness: v.handedness,
+            joints: influences.joints,
+            weights: influences.weights,
         }
     }
 }
-#[repr(C)]
-#[derive(Debug)]
-pub struct OuterMotor {
-    pub position: [f32; 3],
-    pub uv: [f32; 2],
-    pub outer_motor: [[f32; 3]; 2],
-}
-impl HasVertexAttributes for OuterMotor {
-    fn attributes() -> Vec<GlslDType> {
-        vec![
-            GlslDType::Vec3,
-            GlslDType::Vec2,
-            GlslDType::Vec3,
-            GlslDType::Vec3,
-        ]
+impl_from_pos_uv_norm_tang!(SkinnedMotor);
+
+vertex_layout! {
+    #[derive(Debug)]
+    pub struct SkinnedMatrix: "SKINNED_MATRIX_ATTRIBUTES" {
+        pub position: [f32; 3] => [Vec3 aPos],
+        pub uv: [f32; 2] => [Vec2 aUV],
+        pub normal: [f32; 3] => [Vec3 aNormal],
+        pub tangent: [f32; 3] => [Vec3 aTangent],
+        pub bitangent: [f32; 3] => [Vec3 aBiTangent],
+        pub joints: [f32; 4] => [Vec4 aJoints],
+        pub weights: [f32; 4] => [Vec4 aWeights],
     }
 }
-impl From<PosUVNormTang> for OuterMotor {
-    fn from(v: PosUVNormTang) -> Self {
-        let tangent: glm::Vec3 = v.tangent.into();
-        let normal: glm::Vec3 = v.normal.into();
-        let bitangent = glm::cross(&normal, &tangent);
-
-        let rotor = ppga::Rotor::from_base(
-            &tangent.normalize().into(),
-            &bitangent.normalize().into(),
-            &normal.normalize().into(),
-        )
-        .normalize()
-        .into();
-
-        let t = ppga::Translator::new(&v.position);
-        let m = t.mul_rotor(&rotor).normalize();
-
+impl From<&All> for SkinnedMatrix {
+    fn from(v: &All) -> Self {
+        let influences = skinning::Influences::chain(v.position);
         Self {
             position: v.position,
             uv: v.uv,
-            outer_motor: m.outer_ln().into(),
+            normal: v.normal,
+            tangent: v.tangent,
+            bitangent: v.bitangent,
+            joints: influences.joints,
+            weights: influences.weights,
         }
     }
 }
+impl_from_pos_uv_norm_tang!(SkinnedMatrix);
+
+/// The rotor that takes tangent space to model space. It always describes
+/// the right handed frame, mirrored frames are handled by the handedness.
+fn tangent_to_model_rotor(v: &PosUVNormTang) -> ppga::Rotor {
+    let tangent: glm::Vec3 = v.tangent.into();
+    let normal: glm::Vec3 = v.normal.into();
+    let bitangent = glm::cross(&normal, &tangent);
+
+    ppga::Rotor::from_base(
+        &tangent.normalize().into(),
+        &bitangent.normalize().into(),
+        &normal.normalize().into(),
+    )
+    .normalize()
+}
+
+/// The rotor of [`tangent_to_model_rotor`] followed by a translation to
+/// the position of the vertex.
+fn tangent_to_model_motor(v: &PosUVNormTang) -> ppga::Motor {
+    let t = ppga::Translator::new(&v.position);
+    t.mul_rotor(&tangent_to_model_rotor(v)).normalize()
+}
+
+fn signed_bitangent(v: &PosUVNormTang) -> [f32; 3] {
+    let tangent: glm::Vec3 = v.tangent.into();
+    let normal: glm::Vec3 = v.normal.into();
+    (glm::cross(&normal, &tangent) * v.handedness).into()
+}
 
 #[repr(C)]
 #[derive(Debug)]
@@ -0,0 +1,106 @@
+//! Expands every shader the way it is loaded at runtime and validates it
+//! with glslang, for every combination of defines it is compiled with: the
+//! attribute define of each vertex layout it is drawn with, each decode
+//! placement, and with and without instancing.
+
+use pthesis::shaders::DecodePlacement;
+use pthesis::{glsl, instancing, layout, suite, techniques};
+
+/// The defines of both stages, the attribute define only goes to the
+/// vertex stage.
+fn defines(
+    placement: DecodePlacement,
+    defines: &[String],
+    vertex: &str,
+    instanced: bool,
+) -> (Vec<String>, Vec<String>) {
+    let mut fs_defines: Vec<String> = placement.define().into_iter().map(String::from).collect();
+    fs_defines.extend(defines.iter().cloned());
+    if instanced {
+        fs_defines.push(instancing::DEFINE.to_string());
+    }
+    let mut vs_defines = fs_defines.clone();
+    vs_defines.push(layout::attribute_define(vertex).to_string());
+    (vs_defines, fs_defines)
+}
+
+fn check_all(checks: Vec<(String, Vec<String>, Vec<String>)>) {
+    let errors: Vec<String> = checks
+        .iter()
+        .filter_map(|(path, vs_defines, fs_defines)| {
+            glsl::check(path, vs_defines, fs_defines).err()
+        })
+        .collect();
+    assert!(errors.is_empty(), "\n{}", errors.join("\n"));
+}
+
+/// The shaders get the blocks of the current layouts when they are loaded,
+/// the committed copy in `app.glsl` is only read, but should not go stale.
+#[test]
+fn generated_attributes_are_current() {
+    let path = "shaders/headers/app.glsl";
+    let source = std::fs::read_to_string(path).expect("Could not read app.glsl");
+    let section =
+        layout::generated_section(&source).expect("app.glsl has no generated attributes section");
+    assert!(
+        source[section] == layout::glsl_blocks(),
+        "The generated section of {} is out of date, it should be:\n{}",
+        path,
+        layout::glsl_blocks()
+    );
+}
+
+#[test]
+fn every_shader_compiles_without_defines() {
+    let checks = std::fs::read_dir("shaders")
+        .expect("Could not read shaders directory")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("glsl"))
+        .map(|path| (path.to_string_lossy().into_owned(), Vec::new(), Vec::new()))
+        .collect();
+    check_all(checks);
+}
+
+/// With the layout of the benchmark and, for the techniques in the app,
+/// with `All`, which the app draws everything with, instanced.
+#[test]
+fn techniques_compile_with_every_layout_and_placement() {
+    let mut checks = Vec::new();
+    for technique in techniques::TECHNIQUES.iter() {
+        let technique_defines: Vec<String> =
+            technique.defines.iter().map(|d| d.to_string()).collect();
+        let mut vertices = vec![technique.vertex];
+        if technique.app {
+            vertices.push("all");
+        }
+        for vertex in vertices.iter() {
+            for placement in DecodePlacement::ALL.iter() {
+                for instanced in [false, true].iter() {
+                    let (vs, fs) = defines(*placement, &technique_defines, vertex, *instanced);
+                    checks.push((technique.shader.to_string(), vs, fs));
+                }
+            }
+        }
+    }
+    check_all(checks);
+}
+
+#[test]
+fn suite_encodings_compile() {
+    let mut checks = Vec::new();
+    for path in ["bench.toml", "workloads.toml", "skinning.toml"].iter() {
+        let suite = suite::Suite::from_path(path);
+        let instanced = suite.workloads.iter().any(|w| w.instances > 1);
+        for encoding in suite.encodings.iter() {
+            for placement in suite.decode_placements.iter() {
+                let (vs, fs) = defines(*placement, &encoding.defines, &encoding.vertex, false);
+                checks.push((encoding.shader.clone(), vs, fs));
+                if instanced {
+                    let (vs, fs) = defines(*placement, &encoding.defines, &encoding.vertex, true);
+                    checks.push((encoding.shader.clone(), vs, fs));
+                }
+            }
+        }
+    }
+    check_all(checks);
+}